@@ -1,16 +1,23 @@
 use js_sys::{Array, ArrayBuffer, JsString, Map, Number, Object, Symbol, Uint8Array};
 use serde::de::value::{MapDeserializer, SeqDeserializer};
 use serde::de::{self, IntoDeserializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::rc::Rc;
 use wasm_bindgen::convert::IntoWasmAbi;
 use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
 
+use crate::js_ref::JS_REF_MAGIC;
 use crate::preserve::PRESERVED_VALUE_MAGIC;
-use crate::{static_str_to_js, Error, ObjectExt, Result};
+use crate::update::is_plain_object;
+use crate::value::VALUE_MAGIC;
+use crate::{static_str_to_js, EnumRepresentation, Error, ObjectExt, RenameRule, Result};
 
 /// Provides [`de::SeqAccess`] from any JS iterator.
 struct SeqAccess {
     iter: js_sys::IntoIter,
+    options: Rc<Options>,
 }
 
 impl<'de> de::SeqAccess<'de> for SeqAccess {
@@ -21,7 +28,9 @@ impl<'de> de::SeqAccess<'de> for SeqAccess {
         seed: T,
     ) -> Result<Option<T::Value>> {
         Ok(match self.iter.next().transpose()? {
-            Some(value) => Some(seed.deserialize(Deserializer::from(value))?),
+            Some(value) => {
+                Some(seed.deserialize(Deserializer::with_options(value, self.options.clone()))?)
+            }
             None => None,
         })
     }
@@ -30,13 +39,15 @@ impl<'de> de::SeqAccess<'de> for SeqAccess {
 /// Provides [`de::MapAccess`] from any JS iterator that returns `[key, value]` pairs.
 struct MapAccess {
     iter: js_sys::IntoIter,
+    options: Rc<Options>,
     next_value: Option<Deserializer>,
 }
 
 impl MapAccess {
-    const fn new(iter: js_sys::IntoIter) -> Self {
+    const fn new(iter: js_sys::IntoIter, options: Rc<Options>) -> Self {
         Self {
             iter,
+            options,
             next_value: None,
         }
     }
@@ -50,7 +61,7 @@ impl<'de> de::MapAccess<'de> for MapAccess {
 
         Ok(match self.iter.next().transpose()? {
             Some(pair) => {
-                let (key, value) = convert_pair(pair);
+                let (key, value) = convert_pair(pair, &self.options);
                 self.next_value = Some(value);
                 Some(seed.deserialize(key)?)
             }
@@ -63,20 +74,225 @@ impl<'de> de::MapAccess<'de> for MapAccess {
     }
 }
 
+/// Source of struct fields: either a plain JS object or an ES2015 `Map` with string keys.
+#[derive(Clone)]
+pub(crate) enum FieldSource {
+    Object(ObjectExt),
+    Map(Map),
+}
+
+impl FieldSource {
+    /// Looks up a field, returning `None` if it's missing altogether.
+    pub(crate) fn get(&self, key: &JsString) -> Option<JsValue> {
+        let value = match self {
+            Self::Object(obj) => {
+                let value = obj.get_with_ref_key(key);
+                // If this value is `undefined`, it might be actually a missing field;
+                // double-check with an `in` operator if so.
+                if value.is_undefined() && !key.js_in(obj) {
+                    return None;
+                }
+                value
+            }
+            Self::Map(map) => {
+                let value = map.get(key);
+                if value.is_undefined() && !map.has(key) {
+                    return None;
+                }
+                value
+            }
+        };
+        Some(value)
+    }
+}
+
+impl FieldSource {
+    /// Checks whether the object has an own property, or the `Map` has an entry, with this key.
+    pub(crate) fn has_own(&self, key: &JsString) -> bool {
+        match self {
+            Self::Object(obj) => Object::has_own(obj.unchecked_ref::<Object>(), key),
+            Self::Map(map) => map.has(key),
+        }
+    }
+
+    /// Returns all the string keys of the object or `Map`.
+    fn string_keys(&self) -> Result<Vec<String>> {
+        match self {
+            Self::Object(obj) => Ok(Object::keys(obj.unchecked_ref::<Object>())
+                .iter()
+                .filter_map(|key| key.as_string())
+                .collect()),
+            Self::Map(map) => Ok(map
+                .keys()
+                .into_iter()
+                .filter_map(|key| key.map(|key| key.as_string()).transpose())
+                .collect::<std::result::Result<_, JsValue>>()?),
+        }
+    }
+}
+
+/// Finds the only candidate whose name is equal to `name` ignoring case.
+///
+/// Multiple matches are reported as errors, since there is no way to choose between them.
+fn find_case_insensitive<T>(
+    what: &str,
+    name: &str,
+    candidates: impl IntoIterator<Item = T>,
+    candidate_name: impl Fn(&T) -> String,
+) -> Result<Option<T>> {
+    let lowercase_name = name.to_lowercase();
+    let mut matches = candidates
+        .into_iter()
+        .map(|candidate| (candidate_name(&candidate), candidate))
+        .filter(|(candidate_name, _)| candidate_name.to_lowercase() == lowercase_name);
+    match (matches.next(), matches.next()) {
+        (None, _) => Ok(None),
+        (Some((_, candidate)), None) => Ok(Some(candidate)),
+        (Some((first, _)), Some((second, _))) => Err(de::Error::custom(format_args!(
+            "ambiguous {what} `{name}`: matches both `{first}` and `{second}`"
+        ))),
+    }
+}
+
+thread_local! {
+    /// Objects and `Map`s whose entries are currently being visited via
+    /// [`de::Deserializer::deserialize_map`], innermost last.
+    ///
+    /// Serde buffers entries of structs with `#[serde(flatten)]` fields, so this is
+    /// the only way for [`crate::rest`] to get back to the original JS values.
+    static MAP_SOURCES: RefCell<Vec<Option<FieldSource>>> = const { RefCell::new(Vec::new()) };
+}
+
+thread_local! {
+    /// Options of the [`Deserializer`] that has just passed a value to [`JsRef`](crate::JsRef),
+    /// so that it can convert the value later the same way.
+    static JS_REF_OPTIONS: RefCell<Option<Rc<Options>>> = const { RefCell::new(None) };
+}
+
+/// Takes the options stored for a [`JsRef`](crate::JsRef) being deserialized, if any.
+pub(crate) fn take_js_ref_options() -> Option<Rc<Options>> {
+    JS_REF_OPTIONS.with(|options| options.borrow_mut().take())
+}
+
+/// Returns the object or `Map` whose entries are currently being deserialized, if any.
+pub(crate) fn current_map_source() -> Option<FieldSource> {
+    MAP_SOURCES.with(|sources| sources.borrow().last().cloned().flatten())
+}
+
+/// Keeps a [`MAP_SOURCES`] entry alive until the map visitor returns.
+struct MapSourceGuard;
+
+impl MapSourceGuard {
+    fn push(source: Option<FieldSource>) -> Self {
+        MAP_SOURCES.with(|sources| sources.borrow_mut().push(source));
+        MapSourceGuard
+    }
+}
+
+impl Drop for MapSourceGuard {
+    fn drop(&mut self) {
+        MAP_SOURCES.with(|sources| sources.borrow_mut().pop());
+    }
+}
+
 struct ObjectAccess {
-    obj: ObjectExt,
-    fields: std::slice::Iter<'static, &'static str>,
+    source: FieldSource,
+    fields: &'static [&'static str],
+    remaining_fields: std::slice::Iter<'static, &'static str>,
+    /// Number of fields found so far, used to detect unknown keys in `Map`s.
+    found: u32,
+    /// Iterator over `Map` keys, created lazily only if there are any unknown ones.
+    unknown_keys: Option<js_sys::IntoIter>,
+    /// All string keys of the source, collected lazily for case-insensitive lookups.
+    own_keys: Option<Vec<String>>,
+    options: Rc<Options>,
     next_value: Option<Deserializer>,
 }
 
 impl ObjectAccess {
-    fn new(obj: ObjectExt, fields: &'static [&'static str]) -> Self {
+    fn new(source: FieldSource, fields: &'static [&'static str], options: Rc<Options>) -> Self {
         Self {
-            obj,
-            fields: fields.iter(),
+            source,
+            fields,
+            remaining_fields: fields.iter(),
+            found: 0,
+            unknown_keys: None,
+            own_keys: None,
+            options,
             next_value: None,
         }
     }
+
+    /// Looks up the value of the given struct field.
+    ///
+    /// If the field is not found by its exact name and `case_insensitive_identifiers` is enabled,
+    /// falls back to searching through all the keys of the source.
+    fn get_field(&mut self, field: &'static str) -> Result<Option<JsValue>> {
+        let name = self.options.rename_all.field_to_js(field);
+        if let Some(value) = self.source.get(&name) {
+            return Ok(Some(value));
+        }
+        if !self.options.case_insensitive_identifiers {
+            return Ok(None);
+        }
+        let own_keys = match &mut self.own_keys {
+            Some(own_keys) => own_keys,
+            own_keys => own_keys.insert(self.source.string_keys()?),
+        };
+        let key = find_case_insensitive("field", &String::from(name), own_keys.iter(), |key| {
+            key.to_string()
+        })?;
+        Ok(key.and_then(|key| self.source.get(&JsString::from(key.as_str()))))
+    }
+
+    /// Checks whether the given `Map` key corresponds to one of the struct fields.
+    fn is_known_key(&self, key: &JsValue) -> bool {
+        if self.options.type_tag.is_some() && key.as_string().as_deref() == self.options.type_tag {
+            return true;
+        }
+        let rename_all = self.options.rename_all;
+        let case_insensitive_key = match key.as_string() {
+            Some(key) if self.options.case_insensitive_identifiers => Some(key.to_lowercase()),
+            _ => None,
+        };
+        self.fields.iter().any(|field| {
+            let name = rename_all.field_to_js(field);
+            match &case_insensitive_key {
+                Some(key) => String::from(name).to_lowercase() == *key,
+                None => JsValue::from(name) == *key,
+            }
+        })
+    }
+
+    /// Returns the next `[key, value]` pair from a `Map` whose key isn't one of the struct fields.
+    ///
+    /// Plain objects can't be enumerated cheaply, so for them we only ever look up the known
+    /// fields, but for `Map`s we can compare sizes and only walk the keys if there are any
+    /// extra ones, which allows `#[serde(deny_unknown_fields)]` to work as expected.
+    ///
+    /// Keys other than strings can't name struct fields, so they are skipped; otherwise
+    /// Serde would reject them, or even interpret numbers as field indices.
+    fn next_unknown_entry(&mut self) -> Result<Option<(JsValue, JsValue)>> {
+        let map = match &self.source {
+            FieldSource::Map(map) if map.size() > self.found => map.clone(),
+            _ => return Ok(None),
+        };
+        let mut keys = self
+            .unknown_keys
+            .take()
+            .unwrap_or_else(|| map.keys().into_iter());
+        let mut result = None;
+        for key in &mut keys {
+            let key = key?;
+            if key.is_string() && !self.is_known_key(&key) {
+                let value = map.get(&key);
+                result = Some((key, value));
+                break;
+            }
+        }
+        self.unknown_keys = Some(keys);
+        Ok(result)
+    }
 }
 
 fn str_deserializer(s: &str) -> de::value::StrDeserializer<'_, Error> {
@@ -89,18 +305,23 @@ impl<'de> de::MapAccess<'de> for ObjectAccess {
     fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
         debug_assert!(self.next_value.is_none());
 
-        for field in &mut self.fields {
-            let js_field = static_str_to_js(field);
-            let next_value = self.obj.get_with_ref_key(&js_field);
-            // If this value is `undefined`, it might be actually a missing field;
-            // double-check with an `in` operator if so.
-            let is_missing_field = next_value.is_undefined() && !js_field.js_in(&self.obj);
-            if !is_missing_field {
-                self.next_value = Some(Deserializer::from(next_value));
+        while let Some(field) = self.remaining_fields.next() {
+            if let Some(next_value) = self.get_field(field)? {
+                self.found += 1;
+                self.next_value =
+                    Some(Deserializer::with_options(next_value, self.options.clone()));
                 return Ok(Some(seed.deserialize(str_deserializer(field))?));
             }
         }
 
+        if let Some((key, value)) = self.next_unknown_entry()? {
+            self.next_value = Some(Deserializer::with_options(value, self.options.clone()));
+            return Ok(Some(seed.deserialize(Deserializer::with_options(
+                key,
+                self.options.clone(),
+            ))?));
+        }
+
         Ok(None)
     }
 
@@ -131,9 +352,7 @@ impl<'de> de::SeqAccess<'de> for PreservedValueAccess {
                     .map(Some)
             }
             Self::OnValue(value) => seed
-                .deserialize(Deserializer {
-                    value: JsValue::from(value.into_abi()),
-                })
+                .deserialize(Deserializer::from(JsValue::from(value.into_abi())))
                 .map(Some),
             Self::Done => Ok(None),
         }
@@ -142,30 +361,315 @@ impl<'de> de::SeqAccess<'de> for PreservedValueAccess {
 
 /// Provides [`de::EnumAccess`] from given JS values for the `tag` and the `payload`.
 struct EnumAccess {
+    name: &'static str,
     tag: Deserializer,
-    payload: Deserializer,
+    payload: VariantPayload,
 }
 
 impl<'de> de::EnumAccess<'de> for EnumAccess {
     type Error = Error;
-    type Variant = Deserializer;
+    type Variant = TypedPayload;
 
     fn variant_seed<V: de::DeserializeSeed<'de>>(
         self,
         seed: V,
     ) -> Result<(V::Value, Self::Variant)> {
-        Ok((seed.deserialize(self.tag)?, self.payload))
+        // The tag holds the original variant name at this point, unless it's invalid,
+        // in which case the seed fails anyway.
+        let type_name = match (self.tag.options.type_tag, self.tag.value.as_string()) {
+            (Some(_), Some(variant)) => Some(format!("{}::{variant}", self.name)),
+            _ => None,
+        };
+        Ok((
+            seed.deserialize(self.tag)?,
+            TypedPayload {
+                payload: self.payload,
+                type_name,
+            },
+        ))
+    }
+}
+
+/// A variant payload together with the `"Enum::Variant"` name that the `type_tag`
+/// of struct variants must match.
+///
+/// Newtype variants aren't checked here, since their payload is tagged with the name
+/// of the wrapped struct and is checked when the latter is deserialized.
+struct TypedPayload {
+    payload: VariantPayload,
+    type_name: Option<String>,
+}
+
+impl<'de> de::VariantAccess<'de> for TypedPayload {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.payload.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.payload.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.payload.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Some(type_name) = &self.type_name {
+            match &self.payload {
+                VariantPayload::Value(payload)
+                | VariantPayload::Tagged {
+                    object: payload, ..
+                }
+                | VariantPayload::Instance(payload) => payload.check_type_tag(type_name)?,
+            };
+        }
+        self.payload.struct_variant(fields, visitor)
+    }
+}
+
+/// Provides [`de::VariantAccess`] for the payload of an enum variant.
+enum VariantPayload {
+    /// A standalone payload value, as in the externally and adjacently tagged representations.
+    Value(Deserializer),
+    /// An object that contains both the tag and the variant fields, as in the internally
+    /// tagged representation.
+    Tagged {
+        object: Deserializer,
+        tag: &'static str,
+    },
+    /// A class instance that determines the variant and holds its payload.
+    Instance(Deserializer),
+}
+
+impl<'de> de::VariantAccess<'de> for VariantPayload {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self {
+            Self::Value(payload) => de::VariantAccess::unit_variant(payload),
+            Self::Tagged { .. } | Self::Instance(_) => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        match self {
+            Self::Value(payload) => de::VariantAccess::newtype_variant_seed(payload, seed),
+            Self::Tagged { object, tag } => {
+                // Make a copy without the tag so that it doesn't end up in the payload
+                // when the latter is a map or a struct with `#[serde(deny_unknown_fields)]`.
+                let copy = if let Some(map) = object.value.dyn_ref::<Map>() {
+                    let copy = Map::new();
+                    map.for_each(&mut |value, key| {
+                        copy.set(&key, &value);
+                    });
+                    copy.delete(&static_str_to_js(tag));
+                    copy.into()
+                } else {
+                    let copy = Object::assign(&Object::new(), object.value.unchecked_ref());
+                    js_sys::Reflect::delete_property(&copy, &static_str_to_js(tag))?;
+                    copy.into()
+                };
+                seed.deserialize(object.nested(copy))
+            }
+            Self::Instance(object) => seed.deserialize(object),
+        }
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::Value(payload) => de::VariantAccess::tuple_variant(payload, len, visitor),
+            Self::Tagged { object, .. } | Self::Instance(object) => object.invalid_type(visitor),
+        }
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Self::Value(payload)
+            | Self::Tagged {
+                object: payload, ..
+            }
+            | Self::Instance(payload) => {
+                de::VariantAccess::struct_variant(payload, fields, visitor)
+            }
+        }
+    }
+}
+
+/// Deserializes string keys of plain JS objects.
+///
+/// JS objects can only have string keys, so, to mirror how the [`Serializer`](crate::Serializer)
+/// stringifies map keys, this parses them into numbers and booleans when such types are requested.
+struct ObjectKeyDeserializer {
+    key: Deserializer,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($name:ident => $visit:ident($ty:ty),)*) => {
+        $(fn $name<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            match self.key.value.as_string().and_then(|s| s.parse::<$ty>().ok()) {
+                Some(v) => visitor.$visit(v),
+                None => self.key.$name(visitor),
+            }
+        })*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ObjectKeyDeserializer {
+    type Error = Error;
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.key.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        // Unit variants serialized as numbers are stringified like other integer keys.
+        match self
+            .key
+            .value
+            .as_string()
+            .and_then(|s| s.parse::<u32>().ok())
+        {
+            Some(index) => self
+                .key
+                .nested(index.into())
+                .deserialize_enum(name, variants, visitor),
+            None => self.key.deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ObjectKeyDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Options that a [`Deserializer`] shares with all the nested ones it creates.
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    enum_representation: EnumRepresentation,
+    rename_all: RenameRule,
+    case_insensitive_identifiers: bool,
+    coerce_numeric_strings: bool,
+    coerce_bool_strings: bool,
+    coerce_numbers_to_128_bit_integers: bool,
+    coerce_bigints_to_floats: bool,
+    unwrap_boxed_primitives: bool,
+    human_readable: bool,
+    type_tag: Option<&'static str>,
+    variants_from_constructor_names: bool,
+    variant_classes: Option<Rc<VariantClasses>>,
+}
+
+/// Prototypes of classes registered for enum variants, keyed by enum name.
+type VariantClasses = HashMap<&'static str, Vec<(Object, &'static str)>>;
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            enum_representation: EnumRepresentation::default(),
+            rename_all: RenameRule::default(),
+            case_insensitive_identifiers: false,
+            coerce_numeric_strings: false,
+            coerce_bool_strings: false,
+            coerce_numbers_to_128_bit_integers: false,
+            coerce_bigints_to_floats: false,
+            unwrap_boxed_primitives: false,
+            human_readable: true,
+            type_tag: None,
+            variants_from_constructor_names: false,
+            variant_classes: None,
+        }
     }
 }
 
-/// A newtype that allows using any [`JsValue`] as a [`de::Deserializer`].
+impl Options {
+    /// Checks whether any of the options changes how structs and enums are read from objects.
+    fn customizes_objects(&self) -> bool {
+        self.rename_all != RenameRule::None
+            || self.case_insensitive_identifiers
+            || self.enum_representation != EnumRepresentation::default()
+            || self.type_tag.is_some()
+            || self.variants_from_constructor_names
+            || self.variant_classes.is_some()
+    }
+}
+
+/// Checks whether the visitor is the one Serde uses to buffer values into its private
+/// `Content` type for `#[serde(flatten)]` fields and untagged or internally tagged enums.
+///
+/// Serde doesn't expose that type, so the visitor is recognized by its description.
+fn is_buffering(visitor: &dyn de::Expected) -> bool {
+    visitor.to_string() == "any value"
+}
+
+/// Checks whether Serde is buffering entries of an object that [`crate::rest`] can read from.
+///
+/// Values that don't fit into the Serde data model, such as `Set`s, functions or typed arrays,
+/// are buffered as `()` in this case, since `rest` picks up the original values anyway.
+fn is_buffering_for_rest(visitor: &dyn de::Expected) -> bool {
+    current_map_source().is_some() && is_buffering(visitor)
+}
+
+/// A [`de::Deserializer`] that allows using any [`JsValue`] as an input.
 pub struct Deserializer {
     value: JsValue,
+    options: Rc<Options>,
 }
 
 impl From<JsValue> for Deserializer {
     fn from(value: JsValue) -> Self {
-        Self { value }
+        Self::with_options(value, Rc::default())
     }
 }
 
@@ -179,13 +683,273 @@ impl<'de> IntoDeserializer<'de, Error> for Deserializer {
     }
 }
 
+/// Splits a [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901) into unescaped segments.
+pub(crate) fn parse_pointer(path: &str) -> Result<Vec<String>> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(path
+        .strip_prefix('/')
+        .ok_or_else(|| Error::new(format_args!("JSON Pointer `{path}` must start with `/`")))?
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Looks up all the given [`parse_pointer`] segments in a JS value one by one.
+///
+/// If a lookup fails, the error points to the failing segment.
+pub(crate) fn resolve(mut value: JsValue, segments: &[String]) -> Result<JsValue> {
+    for (i, segment) in segments.iter().enumerate() {
+        value = lookup(&value, segment).map_err(|err| {
+            segments[..=i]
+                .iter()
+                .rev()
+                .fold(err, |err, segment| err.at(segment))
+        })?;
+    }
+    Ok(value)
+}
+
+/// Finds the key of an existing `Map` entry that a pointer segment refers to.
+pub(crate) fn find_map_key(map: &Map, segment: &str) -> Option<JsValue> {
+    let key = JsValue::from_str(segment);
+    if map.has(&key) {
+        return Some(key);
+    }
+    // Maps serialized from Rust can have numeric keys too.
+    segment
+        .parse::<f64>()
+        .map(JsValue::from_f64)
+        .ok()
+        .filter(|key| map.has(key))
+}
+
+/// Parses an array index segment, which has to be `0` or a number without leading zeros
+/// or signs according to RFC 6901.
+pub(crate) fn parse_array_index(segment: &str) -> Option<u32> {
+    let canonical = segment == "0"
+        || (!segment.starts_with('0')
+            && !segment.is_empty()
+            && segment.bytes().all(|b| b.is_ascii_digit()));
+    if canonical {
+        segment.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Looks up a single pointer segment in a JS value.
+fn lookup(value: &JsValue, segment: &str) -> Result<JsValue> {
+    if let Some(map) = value.dyn_ref::<Map>() {
+        return match find_map_key(map, segment) {
+            Some(key) => Ok(map.get(&key)),
+            None => Err(Error::new(format_args!("missing Map entry `{segment}`"))),
+        };
+    }
+    if let Some(array) = value.dyn_ref::<Array>() {
+        let index = parse_array_index(segment)
+            .ok_or_else(|| Error::new(format_args!("invalid array index `{segment}`")))?;
+        if index >= array.length() {
+            return Err(Error::new(format_args!(
+                "array index {index} is out of bounds for length {}",
+                array.length()
+            )));
+        }
+        return Ok(array.get(index));
+    }
+    if value.is_object() {
+        let key = JsString::from(segment);
+        // Only own properties, so that e.g. `/constructor` doesn't resolve to inherited ones.
+        if !Object::has_own(value.unchecked_ref::<Object>(), &key) {
+            return Err(Error::new(format_args!("missing property `{segment}`")));
+        }
+        return Ok(value.unchecked_ref::<ObjectExt>().get_with_ref_key(&key));
+    }
+    let kind = if value.is_null() {
+        "null".to_owned()
+    } else {
+        value.js_typeof().as_string().unwrap_throw()
+    };
+    Err(Error::new(format_args!(
+        "can't look up `{segment}` in {kind}"
+    )))
+}
+
 /// Destructures a JS `[key, value]` pair into a tuple of [`Deserializer`]s.
-fn convert_pair(pair: JsValue) -> (Deserializer, Deserializer) {
+fn convert_pair(pair: JsValue, options: &Rc<Options>) -> (Deserializer, Deserializer) {
     let pair = pair.unchecked_into::<Array>();
-    (pair.get(0).into(), pair.get(1).into())
+    (
+        Deserializer::with_options(pair.get(0), options.clone()),
+        Deserializer::with_options(pair.get(1), options.clone()),
+    )
 }
 
 impl Deserializer {
+    pub(crate) const fn with_options(value: JsValue, options: Rc<Options>) -> Self {
+        Self { value, options }
+    }
+
+    /// Creates a [`Deserializer`] for a nested value with the same options.
+    fn nested(&self, value: JsValue) -> Self {
+        Self::with_options(value, self.options.clone())
+    }
+
+    /// Sets how enums without explicit Serde tagging attributes are expected to be represented.
+    ///
+    /// This should match the [`Serializer::enum_representation`](crate::Serializer::enum_representation)
+    /// option used to produce the value. Unit variants are accepted as plain strings
+    /// in all representations. [`EnumRepresentation::ExternallyTagged`] by default.
+    pub fn enum_representation(mut self, value: EnumRepresentation) -> Self {
+        Rc::make_mut(&mut self.options).enum_representation = value;
+        self
+    }
+
+    /// Sets the case convention that struct field names and enum variant names are expected in.
+    ///
+    /// This should match the [`Serializer::rename_all`](crate::Serializer::rename_all)
+    /// option used to produce the value. [`RenameRule::None`] by default.
+    ///
+    /// Like other options for structs and enums, this can't be applied to values that Serde
+    /// buffers, so objects in `#[serde(flatten)]` fields or untagged or internally tagged enums
+    /// are rejected while it's set.
+    pub fn rename_all(mut self, value: RenameRule) -> Self {
+        Rc::make_mut(&mut self.options).rename_all = value;
+        self
+    }
+
+    /// Set to `true` to match struct field names and enum variant names case-insensitively
+    /// if there is no exact match, so that e.g. `Id` and `ID` properties are accepted for an `id` field,
+    /// and `"ACTIVE"` for an `Active` variant. `false` by default.
+    ///
+    /// If several properties or variants match a name this way, deserialization fails
+    /// instead of picking one of them arbitrarily.
+    pub fn case_insensitive_identifiers(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).case_insensitive_identifiers = value;
+        self
+    }
+
+    /// Set to `true` to parse strings like `"42"` or `"1.5"` when an integer or
+    /// a float is expected, e.g. for values coming from forms or `URLSearchParams`.
+    /// `false` by default.
+    pub fn coerce_numeric_strings(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).coerce_numeric_strings = value;
+        self
+    }
+
+    /// Set to `true` to accept `"true"` and `"false"` strings when a `bool` is expected.
+    /// `false` by default.
+    pub fn coerce_bool_strings(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).coerce_bool_strings = value;
+        self
+    }
+
+    /// Set to `true` to accept numbers within the safe integer range when an `i128` or `u128`
+    /// is expected, not just `BigInt`s. `false` by default.
+    pub fn coerce_numbers_to_128_bit_integers(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).coerce_numbers_to_128_bit_integers = value;
+        self
+    }
+
+    /// Set to `true` to accept `BigInt`s when a float is expected, as long as they can be
+    /// represented as `f64` exactly. `false` by default.
+    pub fn coerce_bigints_to_floats(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).coerce_bigints_to_floats = value;
+        self
+    }
+
+    /// Set to `true` to unwrap boxed primitives created via `new Number(...)`,
+    /// `new String(...)` or `new Boolean(...)` into their primitive values. `false` by default.
+    pub fn unwrap_boxed_primitives(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).unwrap_boxed_primitives = value;
+        self
+    }
+
+    /// Sets the value returned from [`de::Deserializer::is_human_readable`]. `true` by default.
+    ///
+    /// Set to `false` to read types like `IpAddr` or `SocketAddr` from their compact
+    /// representation instead of strings. This should match the
+    /// [`Serializer::human_readable`](crate::Serializer::human_readable) option used to produce the value.
+    pub fn human_readable(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).human_readable = value;
+        self
+    }
+
+    /// Sets the property name that holds Rust type names, as written by
+    /// [`Serializer::type_tag`](crate::Serializer::type_tag). `None` by default.
+    ///
+    /// When set, the tags found on structs, unit structs and struct variants must match
+    /// the expected type, otherwise deserialization fails with an error naming both types.
+    /// Objects without a tag are still accepted.
+    pub fn type_tag(mut self, key: Option<&'static str>) -> Self {
+        Rc::make_mut(&mut self.options).type_tag = key;
+        self
+    }
+
+    /// Set to `true` to pick enum variants by the constructor name of class instances.
+    /// `false` by default.
+    ///
+    /// This is useful for sum types modelled as class hierarchies on the JS side: an instance
+    /// of `class Circle extends Shape` is read as the `Circle` variant, and the payload is read
+    /// from the instance itself. Constructor names are matched like other variant names, so
+    /// [`Deserializer::rename_all`] and [`Deserializer::case_insensitive_identifiers`] apply.
+    /// Plain objects and instances of other classes are read as usual.
+    ///
+    /// Bundlers may rename classes while minifying, in which case
+    /// [`Deserializer::variant_class`] is a more reliable alternative.
+    pub fn variants_from_constructor_names(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.options).variants_from_constructor_names = value;
+        self
+    }
+
+    /// Registers a JS class for a variant of the enum with the given name, so that values
+    /// that are `instanceof` this class are read as that variant, with the payload read from
+    /// the instance itself.
+    ///
+    /// Classes are checked in the order they were registered, before
+    /// [`Deserializer::variants_from_constructor_names`], so subclasses should be registered
+    /// before their base classes.
+    ///
+    /// Fails if the constructor doesn't have a `prototype` object, as is the case for arrow
+    /// functions and methods.
+    pub fn variant_class(
+        mut self,
+        enum_name: &'static str,
+        variant: &'static str,
+        constructor: &js_sys::Function,
+    ) -> Result<Self> {
+        let prototype = js_sys::Reflect::get(constructor, &static_str_to_js("prototype"))?
+            .dyn_into::<Object>()
+            .map_err(|_| {
+                Error::new(format_args!(
+                    "class constructor for `{enum_name}::{variant}` must have a `prototype` object"
+                ))
+            })?;
+        Rc::make_mut(
+            Rc::make_mut(&mut self.options)
+                .variant_classes
+                .get_or_insert_with(Default::default),
+        )
+        .entry(enum_name)
+        .or_default()
+        .push((prototype, variant));
+        Ok(self)
+    }
+
+    /// Returns a [`Deserializer`] for the value at the given [JSON Pointer], such as
+    /// `/features/flags/0`, with the same options.
+    ///
+    /// Object properties, `Map` entries and array elements along the path are looked up
+    /// directly in JS, so nothing except the target value is converted. An empty path refers
+    /// to the value itself. If a segment is missing or can't be looked up in the value
+    /// it's applied to, the error points to that segment.
+    ///
+    /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub fn pointer(self, path: &str) -> Result<Self> {
+        let value = resolve(self.value, &parse_pointer(path)?)?;
+        Ok(Self::with_options(value, self.options))
+    }
+
     fn as_object_entries(&self) -> Option<Array> {
         if self.value.is_object() {
             Some(Object::entries(self.value.unchecked_ref()))
@@ -242,6 +1006,79 @@ impl Deserializer {
         Err(self.invalid_type_(&visitor))
     }
 
+    /// Returns the `type_tag` property name and its value if the option is set
+    /// and the value is an object or `Map` that has such a property.
+    fn get_type_tag(&self) -> Option<(&'static str, JsValue)> {
+        let key = self.options.type_tag?;
+        let source = if let Some(map) = self.value.dyn_ref::<Map>() {
+            FieldSource::Map(map.clone())
+        } else if self.value.is_object() && !Array::is_array(&self.value) {
+            FieldSource::Object(self.value.clone().unchecked_into())
+        } else {
+            return None;
+        };
+        Some((key, source.get(&static_str_to_js(key))?))
+    }
+
+    /// Picks the enum variant for a class instance according to the registered
+    /// variant classes and `variants_from_constructor_names`.
+    fn variant_from_class(
+        &self,
+        name: &'static str,
+        variants: &'static [&'static str],
+    ) -> Result<Option<JsValue>> {
+        if self.options.variant_classes.is_none() && !self.options.variants_from_constructor_names {
+            return Ok(None);
+        }
+        if !self.value.is_object() || Array::is_array(&self.value) {
+            return Ok(None);
+        }
+        if let Some(classes) = &self.options.variant_classes
+            && let Some(classes) = classes.get(name)
+        {
+            let class = classes
+                .iter()
+                .find(|(prototype, _)| prototype.is_prototype_of(&self.value));
+            if let Some((_, variant)) = class {
+                return Ok(Some(static_str_to_js(variant).into()));
+            }
+        }
+        if !self.options.variants_from_constructor_names || is_plain_object(&self.value) {
+            return Ok(None);
+        }
+        let constructor = js_sys::Reflect::get(&self.value, &static_str_to_js("constructor"))?;
+        if !constructor.is_function() {
+            return Ok(None);
+        }
+        let class_name = js_sys::Reflect::get(&constructor, &static_str_to_js("name"))?;
+        if !class_name.is_string() {
+            return Ok(None);
+        }
+        let variant = self.variant_name(class_name, variants)?;
+        Ok(variant
+            .as_string()
+            .is_some_and(|variant| variants.contains(&variant.as_str()))
+            .then_some(variant))
+    }
+
+    /// Fails if the value has a `type_tag` property that isn't equal to `expected`.
+    ///
+    /// Returns whether the property was present.
+    fn check_type_tag(&self, expected: &str) -> Result<bool> {
+        let Some((key, tag)) = self.get_type_tag() else {
+            return Ok(false);
+        };
+        match tag.as_string() {
+            Some(tag) if tag == expected => Ok(true),
+            Some(tag) => Err(Error::new(format_args!(
+                "expected `{key}` to be `{expected}`, found `{tag}`"
+            ))),
+            None => Err(Error::new(format_args!(
+                "expected `{key}` to be `{expected}`, found {tag:?}"
+            ))),
+        }
+    }
+
     fn as_safe_integer(&self) -> Option<i64> {
         if Number::is_safe_integer(&self.value) {
             return Some(self.value.unchecked_into_f64() as i64);
@@ -249,11 +1086,68 @@ impl Deserializer {
         None
     }
 
+    /// Replaces a boxed `Number`, `String` or `Boolean` with its primitive value
+    /// if `unwrap_boxed_primitives` is enabled.
+    fn unbox(&mut self) {
+        if self.options.unwrap_boxed_primitives
+            && self.value.is_object()
+            && (self.value.is_instance_of::<Number>()
+                || self.value.is_instance_of::<JsString>()
+                || self.value.is_instance_of::<js_sys::Boolean>())
+        {
+            self.value = self.value.unchecked_ref::<Object>().value_of().into();
+        }
+    }
+
+    /// Parses a numeric string if `coerce_numeric_strings` is enabled.
+    fn parse_numeric_string<T: std::str::FromStr>(&self) -> Option<T> {
+        if !self.options.coerce_numeric_strings {
+            return None;
+        }
+        self.value.as_string()?.parse().ok()
+    }
+
+    /// Parses the string form of non-finite floats produced by [`NonFiniteFloats::String`].
+    ///
+    /// [`NonFiniteFloats::String`]: crate::NonFiniteFloats::String
+    fn as_non_finite_float(&self) -> Option<f64> {
+        match self.value.as_string()?.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        }
+    }
+
+    /// Converts a `BigInt` to `f64` if `coerce_bigints_to_floats` is enabled and
+    /// the conversion is exact.
+    fn bigint_as_f64(&self) -> Option<Result<f64>> {
+        if !self.options.coerce_bigints_to_floats || !self.value.is_bigint() {
+            return None;
+        }
+        let v = match i128::try_from(self.value.clone()) {
+            Ok(v) => v as f64,
+            Err(value) => u128::try_from(value).map_or(f64::NAN, |v| v as f64),
+        };
+        // Loose equality compares `BigInt`s and numbers by their mathematical values,
+        // so this catches both rounding and out-of-range values.
+        Some(if JsValue::from(v).loose_eq(&self.value) {
+            Ok(v)
+        } else {
+            Err(de::Error::custom(
+                "Couldn't deserialize f64 from a BigInt that can't be represented exactly",
+            ))
+        })
+    }
+
     fn deserialize_from_js_number_signed<'de, V: de::Visitor<'de>>(
         &self,
         visitor: V,
     ) -> Result<V::Value> {
-        match self.as_safe_integer() {
+        match self
+            .as_safe_integer()
+            .or_else(|| self.parse_numeric_string())
+        {
             Some(v) => visitor.visit_i64(v),
             _ => self.invalid_type(visitor),
         }
@@ -265,7 +1159,81 @@ impl Deserializer {
     ) -> Result<V::Value> {
         match self.as_safe_integer() {
             Some(v) if v >= 0 => visitor.visit_u64(v as _),
-            _ => self.invalid_type(visitor),
+            Some(_) => self.invalid_type(visitor),
+            None => match self.parse_numeric_string() {
+                Some(v) => visitor.visit_u64(v),
+                None => self.invalid_type(visitor),
+            },
+        }
+    }
+
+    fn deserialize_entries<'de, V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match js_sys::try_iter(&self.value)? {
+            Some(iter) => visitor.visit_map(MapAccess::new(iter, self.options)),
+            None => match self.as_object_entries() {
+                Some(arr) => visitor.visit_map(MapDeserializer::new(arr.iter().map(|pair| {
+                    let (key, value) = convert_pair(pair, &self.options);
+                    (ObjectKeyDeserializer { key }, value)
+                }))),
+                None => self.invalid_type(visitor),
+            },
+        }
+    }
+
+    /// Returns the source of the tag and content of an internally or adjacently tagged enum,
+    /// or `None` if the value is not an object or a `Map` at all.
+    fn tagged_source(&self) -> Option<FieldSource> {
+        if let Some(map) = self.value.dyn_ref::<Map>() {
+            Some(FieldSource::Map(map.clone()))
+        } else if self.value.is_object() {
+            Some(FieldSource::Object(self.value.clone().unchecked_into()))
+        } else {
+            None
+        }
+    }
+
+    /// Reads the tag property of an internally or adjacently tagged enum.
+    fn get_tag(source: &FieldSource, tag: &'static str) -> Result<JsValue> {
+        source
+            .get(&static_str_to_js(tag))
+            .ok_or_else(|| de::Error::missing_field(tag))
+    }
+
+    /// Converts a numeric variant index, or a renamed or differently cased variant,
+    /// into the original variant name,
+    /// passing other values through.
+    fn variant_name(&self, value: JsValue, variants: &'static [&'static str]) -> Result<JsValue> {
+        let rename_all = self.options.rename_all;
+        let case_insensitive = self.options.case_insensitive_identifiers;
+        if value.is_string() && (rename_all != RenameRule::None || case_insensitive) {
+            let mut variant = variants
+                .iter()
+                .find(|variant| JsValue::from(rename_all.variant_to_js(variant)) == value);
+            if variant.is_none() && case_insensitive {
+                variant = find_case_insensitive(
+                    "variant",
+                    &value.as_string().unwrap_throw(),
+                    variants,
+                    |variant| rename_all.variant_to_js(variant).into(),
+                )?;
+            }
+            return Ok(match variant {
+                Some(variant) => static_str_to_js(variant).into(),
+                None => value,
+            });
+        }
+        let index = match value.as_f64() {
+            Some(index) => index,
+            None => return Ok(value),
+        };
+        match variants.get(index as usize) {
+            Some(variant) if index.fract() == 0.0 && index >= 0.0 => {
+                Ok(static_str_to_js(variant).into())
+            }
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Float(index),
+                &format!("variant index 0 <= i < {}", variants.len()).as_str(),
+            )),
         }
     }
 
@@ -274,14 +1242,17 @@ impl Deserializer {
         visitor: V,
         array: &Array,
     ) -> Result<V::Value> {
-        visitor.visit_seq(SeqDeserializer::new(array.iter().map(Deserializer::from)))
+        visitor.visit_seq(SeqDeserializer::new(
+            array.iter().map(|value| self.nested(value)),
+        ))
     }
 }
 
 impl<'de> de::Deserializer<'de> for Deserializer {
     type Error = Error;
 
-    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_any<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if self.is_nullish() {
             // Ideally we would only treat `undefined` as `()` / `None` which would be semantically closer
             // to JS definitions, but, unfortunately, WebIDL generates missing values as `null`
@@ -294,7 +1265,14 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                 Ok(v) => visitor.visit_i64(v),
                 Err(value) => match u64::try_from(value) {
                     Ok(v) => visitor.visit_u64(v),
-                    Err(_) => Err(de::Error::custom("Couldn't deserialize i64 or u64 from a BigInt outside i64::MIN..u64::MAX bounds"))
+                    Err(value) => match i128::try_from(value) {
+                        Ok(v) => visitor.visit_i128(v),
+                        Err(value) => match u128::try_from(value) {
+                            Ok(v) => visitor.visit_u128(v),
+                            Err(_) if is_buffering_for_rest(&visitor) => visitor.visit_unit(),
+                            Err(_) => Err(de::Error::custom("Couldn't deserialize i128 or u128 from a BigInt outside i128::MIN..u128::MAX bounds"))
+                        }
+                    }
                 }
             }
         } else if let Some(v) = self.value.as_f64() {
@@ -327,7 +1305,20 @@ impl<'de> de::Deserializer<'de> for Deserializer {
             // https://github.com/serde-rs/serde/issues/1183 is implemented / fixed on serde side.
             (!Symbol::iterator().js_in(&self.value) || self.value.has_type::<Map>())
         {
-            self.deserialize_map(visitor)
+            // Buffered objects are later deserialized by Serde itself, which knows nothing about
+            // our options, while the `Serializer` applies them. Fail instead of silently
+            // ignoring renamed fields or differently represented enums in such objects.
+            if self.options.customizes_objects() && is_buffering(&visitor) {
+                return Err(de::Error::custom(
+                    "Deserializer options for struct fields and enums can't be applied to objects \
+                     in #[serde(flatten)] fields or untagged or internally tagged enums",
+                ));
+            }
+            // Values visited via `deserialize_any` are usually buffered by Serde, so they
+            // shouldn't be registered as a source for `serde_wasm_bindgen::rest`.
+            self.deserialize_entries(visitor)
+        } else if is_buffering_for_rest(&visitor) {
+            visitor.visit_unit()
         } else {
             self.invalid_type(visitor)
         }
@@ -341,20 +1332,32 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         }
     }
 
+    /// Unit structs are accepted as `undefined` or `null`, or as `{ [type_tag]: name }`
+    /// if `type_tag` is set.
     fn deserialize_unit_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
+        if self.check_type_tag(name)? {
+            return visitor.visit_unit();
+        }
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_bool<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if let Some(v) = self.value.as_bool() {
-            visitor.visit_bool(v)
-        } else {
-            self.invalid_type(visitor)
+            return visitor.visit_bool(v);
+        }
+        if self.options.coerce_bool_strings {
+            match self.value.as_string().as_deref() {
+                Some("true") => return visitor.visit_bool(true),
+                Some("false") => return visitor.visit_bool(false),
+                _ => {}
+            }
         }
+        self.invalid_type(visitor)
     }
 
     // Serde happily converts `f64` to `f32` (with checks), so we can forward.
@@ -362,9 +1365,16 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         self.deserialize_f64(visitor)
     }
 
-    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_f64<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if let Some(v) = self.value.as_f64() {
             visitor.visit_f64(v)
+        } else if let Some(v) = self.as_non_finite_float() {
+            visitor.visit_f64(v)
+        } else if let Some(v) = self.bigint_as_f64() {
+            visitor.visit_f64(v?)
+        } else if let Some(v) = self.parse_numeric_string() {
+            visitor.visit_f64(v)
         } else {
             self.invalid_type(visitor)
         }
@@ -378,7 +1388,8 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_string<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if let Some(v) = self.value.as_string() {
             visitor.visit_string(v)
         } else {
@@ -389,34 +1400,42 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     // Serde happily converts any integer to any integer (with checks), so let's forward all of
     // these to 64-bit methods to save some space in the generated WASM.
 
-    fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_i8<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_signed(visitor)
     }
 
-    fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_i16<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_signed(visitor)
     }
 
-    fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_i32<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_signed(visitor)
     }
 
-    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_u8<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_unsigned(visitor)
     }
 
-    fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_u16<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_unsigned(visitor)
     }
 
-    fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_u32<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         self.deserialize_from_js_number_unsigned(visitor)
     }
 
     /// Supported inputs:
     /// - `BigInt` within `i64` boundaries.
     /// - number within safe integer boundaries.
-    fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    /// - decimal string if `coerce_numeric_strings` is enabled.
+    fn deserialize_i64<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if self.value.is_bigint() {
             match i64::try_from(self.value) {
                 Ok(v) => visitor.visit_i64(v),
@@ -432,7 +1451,9 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     /// Supported inputs:
     /// - `BigInt` within `u64` boundaries.
     /// - number within safe integer boundaries.
-    fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    /// - decimal string if `coerce_numeric_strings` is enabled.
+    fn deserialize_u64<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if self.value.is_bigint() {
             match u64::try_from(self.value) {
                 Ok(v) => visitor.visit_u64(v),
@@ -447,7 +1468,10 @@ impl<'de> de::Deserializer<'de> for Deserializer {
 
     /// Supported inputs:
     /// - `BigInt` within `i128` boundaries.
-    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    /// - number within safe integer boundaries if `coerce_numbers_to_128_bit_integers` is enabled.
+    /// - decimal string if `coerce_numeric_strings` is enabled.
+    fn deserialize_i128<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if self.value.is_bigint() {
             match i128::try_from(self.value) {
                 Ok(v) => visitor.visit_i128(v),
@@ -455,6 +1479,13 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                     "Couldn't deserialize i128 from a BigInt outside i128::MIN..i128::MAX bounds",
                 )),
             }
+        } else if let Some(v) = self
+            .as_safe_integer()
+            .filter(|_| self.options.coerce_numbers_to_128_bit_integers)
+        {
+            visitor.visit_i128(v.into())
+        } else if let Some(v) = self.parse_numeric_string() {
+            visitor.visit_i128(v)
         } else {
             self.invalid_type(visitor)
         }
@@ -462,7 +1493,10 @@ impl<'de> de::Deserializer<'de> for Deserializer {
 
     /// Supported inputs:
     /// - `BigInt` within `u128` boundaries.
-    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    /// - number within safe integer boundaries if `coerce_numbers_to_128_bit_integers` is enabled.
+    /// - decimal string if `coerce_numeric_strings` is enabled.
+    fn deserialize_u128<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if self.value.is_bigint() {
             match u128::try_from(self.value) {
                 Ok(v) => visitor.visit_u128(v),
@@ -470,6 +1504,13 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                     "Couldn't deserialize u128 from a BigInt outside u128::MIN..u128::MAX bounds",
                 )),
             }
+        } else if let Some(v) = self
+            .as_safe_integer()
+            .filter(|v| *v >= 0 && self.options.coerce_numbers_to_128_bit_integers)
+        {
+            visitor.visit_u128(v as _)
+        } else if let Some(v) = self.parse_numeric_string() {
+            visitor.visit_u128(v)
         } else {
             self.invalid_type(visitor)
         }
@@ -480,7 +1521,8 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     /// By default we don't perform detection of single chars because it's pretty complicated,
     /// but if we get a hint that they're expected, this methods allows to avoid heap allocations
     /// of an intermediate `String` by directly converting numeric codepoints instead.
-    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_char<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.unbox();
         if let Some(s) = self.value.dyn_ref::<JsString>() {
             if let Some(c) = s.as_char() {
                 return visitor.visit_char(c);
@@ -503,9 +1545,22 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     /// Forwards to deserializing newtype contents.
     fn deserialize_newtype_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
+        if name == VALUE_MAGIC {
+            // Pass the value through to `serde_wasm_bindgen::Value` as a magic enum variant.
+            let tag = self.nested(JsValue::from_str(VALUE_MAGIC));
+            let abi = self.nested(JsValue::from(self.value.clone().into_abi()));
+            return visitor.visit_enum(EnumAccess {
+                name,
+                tag,
+                payload: VariantPayload::Value(abi),
+            });
+        }
+        if name == JS_REF_MAGIC {
+            JS_REF_OPTIONS.with(|options| *options.borrow_mut() = Some(self.options.clone()));
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -518,7 +1573,10 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         if let Some(arr) = self.value.dyn_ref::<Array>() {
             self.deserialize_from_array(visitor, arr)
         } else if let Some(iter) = js_sys::try_iter(&self.value)? {
-            visitor.visit_seq(SeqAccess { iter })
+            visitor.visit_seq(SeqAccess {
+                iter,
+                options: self.options,
+            })
         } else {
             self.invalid_type(visitor)
         }
@@ -550,57 +1608,123 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     ///  - A Rust key-value map ([`HashMap`](std::collections::HashMap), [`BTreeMap`](std::collections::BTreeMap), etc.).
     ///  - A typed Rust structure with `#[derive(Deserialize)]`.
     fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        match js_sys::try_iter(&self.value)? {
-            Some(iter) => visitor.visit_map(MapAccess::new(iter)),
-            None => match self.as_object_entries() {
-                Some(arr) => visitor.visit_map(MapDeserializer::new(arr.iter().map(convert_pair))),
-                None => self.invalid_type(visitor),
-            },
-        }
+        let source = if self.value.has_type::<Map>() {
+            Some(FieldSource::Map(self.value.clone().unchecked_into()))
+        } else if self.value.is_object() && !Symbol::iterator().js_in(&self.value) {
+            Some(FieldSource::Object(self.value.clone().unchecked_into()))
+        } else {
+            None
+        };
+        let _guard = MapSourceGuard::push(source);
+        self.deserialize_entries(visitor)
     }
 
     /// Supported inputs:
     ///  - A plain JS object.
+    ///  - An ES2015 `Map` with string keys.
+    ///  - A JS array of field values in declaration order, as produced with
+    ///    [`Serializer::serialize_structs_as_arrays`](crate::Serializer::serialize_structs_as_arrays).
     ///
     /// Supported outputs:
     ///  - A typed Rust structure with `#[derive(Deserialize)]`.
     fn deserialize_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        let obj = if self.value.is_object() {
-            self.value.unchecked_into::<ObjectExt>()
+        // Struct variants are passed without a name and checked in `TypedPayload` instead.
+        if !name.is_empty() {
+            self.check_type_tag(name)?;
+        }
+        if let Some(array) = self.value.dyn_ref::<Array>() {
+            return self.deserialize_from_array(visitor, array);
+        }
+        let source = if self.value.has_type::<Map>() {
+            FieldSource::Map(self.value.unchecked_into())
+        } else if self.value.is_object() {
+            FieldSource::Object(self.value.unchecked_into())
         } else {
             return self.invalid_type(visitor);
         };
-        visitor.visit_map(ObjectAccess::new(obj, fields))
+        visitor.visit_map(ObjectAccess::new(source, fields, self.options))
     }
 
     /// Here we try to be compatible with `serde-json`, which means supporting:
     ///  - `"Variant"` - gets converted to a unit variant `MyEnum::Variant`
     ///  - `{ Variant: ...payload... }` - gets converted to a `MyEnum::Variant { ...payload... }`.
+    ///
+    /// Additionally, variant indices are accepted in place of `"Variant"` names
+    /// for compatibility with TypeScript numeric enums.
+    ///
+    /// If a different [`EnumRepresentation`] is configured, objects are expected in that form instead:
+    ///  - `{ [tag]: "Variant", ...fields... }` for internally tagged enums.
+    ///  - `{ [tag]: "Variant", [content]: ...payload... }` for adjacently tagged enums.
+    ///
+    /// Instances of classes registered with [`Deserializer::variant_class`], or of classes named
+    /// after variants if `variants_from_constructor_names` is set, are read as those variants
+    /// in any representation, with the payload read from the instance itself.
     fn deserialize_enum<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        let access = if self.value.is_string() {
+        let access = if let Some(variant) = self.variant_from_class(name, variants)? {
             EnumAccess {
-                tag: self.value.into(),
-                payload: JsValue::UNDEFINED.into(),
+                name,
+                tag: self.nested(variant),
+                payload: VariantPayload::Instance(self),
             }
-        } else if let Some(entries) = self.as_object_entries() {
-            if entries.length() != 1 {
-                return Err(de::Error::invalid_length(entries.length() as _, &"1"));
+        } else if self.value.is_string() || self.value.as_f64().is_some() {
+            EnumAccess {
+                name,
+                tag: self.nested(self.variant_name(self.value.clone(), variants)?),
+                payload: VariantPayload::Value(self.nested(JsValue::UNDEFINED)),
             }
-            let entry = entries.get(0);
-            let (tag, payload) = convert_pair(entry);
-            EnumAccess { tag, payload }
         } else {
-            return self.invalid_type(visitor);
+            match self.options.enum_representation {
+                EnumRepresentation::ExternallyTagged => {
+                    let entries = match self.as_object_entries() {
+                        Some(entries) => entries,
+                        None => return self.invalid_type(visitor),
+                    };
+                    if entries.length() != 1 {
+                        return Err(de::Error::invalid_length(entries.length() as _, &"1"));
+                    }
+                    let entry = entries.get(0).unchecked_into::<Array>();
+                    EnumAccess {
+                        name,
+                        tag: self.nested(self.variant_name(entry.get(0), variants)?),
+                        payload: VariantPayload::Value(self.nested(entry.get(1))),
+                    }
+                }
+                EnumRepresentation::InternallyTagged { tag } => {
+                    let Some(source) = self.tagged_source() else {
+                        return self.invalid_type(visitor);
+                    };
+                    let tag_value = Self::get_tag(&source, tag)?;
+                    EnumAccess {
+                        name,
+                        tag: self.nested(self.variant_name(tag_value, variants)?),
+                        payload: VariantPayload::Tagged { object: self, tag },
+                    }
+                }
+                EnumRepresentation::AdjacentlyTagged { tag, content } => {
+                    let Some(source) = self.tagged_source() else {
+                        return self.invalid_type(visitor);
+                    };
+                    let tag_value = Self::get_tag(&source, tag)?;
+                    let content = source
+                        .get(&static_str_to_js(content))
+                        .unwrap_or(JsValue::UNDEFINED);
+                    EnumAccess {
+                        name,
+                        tag: self.nested(self.variant_name(tag_value, variants)?),
+                        payload: VariantPayload::Value(self.nested(content)),
+                    }
+                }
+            }
         };
         visitor.visit_enum(access)
     }
@@ -632,7 +1756,7 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     }
 
     fn is_human_readable(&self) -> bool {
-        true
+        self.options.human_readable
     }
 }
 
@@ -1,4 +1,5 @@
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 /// A newtype that represents Serde errors as JavaScript exceptions.
 #[derive(Debug)]
@@ -23,6 +24,37 @@ impl Error {
     pub fn new<T: std::fmt::Display>(msg: T) -> Self {
         Error(JsError::new(&msg.to_string()).into())
     }
+
+    /// Prepends a segment to the [JSON Pointer] of the location where this error happened.
+    ///
+    /// The resulting pointer is appended to the error message and is also available
+    /// as a `path` property on the JavaScript error.
+    ///
+    /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub(crate) fn at(self, segment: impl std::fmt::Display) -> Self {
+        let error = match self.0.dyn_ref::<js_sys::Error>() {
+            Some(error) => error,
+            None => return self,
+        };
+        let path_key = JsValue::from_str("path");
+        let old_path = js_sys::Reflect::get(error, &path_key)
+            .ok()
+            .and_then(|path| path.as_string());
+        let segment = segment.to_string().replace('~', "~0").replace('/', "~1");
+        let path = format!("/{segment}{}", old_path.as_deref().unwrap_or_default());
+
+        let message = String::from(error.message());
+        let message = match &old_path {
+            Some(old_path) => message
+                .strip_suffix(&format!(" at {old_path}"))
+                .unwrap_or(&message),
+            None => &message,
+        };
+        error.set_message(&format!("{message} at {path}"));
+        // Failing to set the property on a frozen error is not a big deal, so ignore it.
+        let _ = js_sys::Reflect::set(error, &path_key, &path.into());
+        self
+    }
 }
 
 impl serde::ser::Error for Error {
@@ -0,0 +1,145 @@
+use serde::de::{self, DeserializeOwned};
+use serde::ser;
+use std::cell::OnceCell;
+use std::rc::Rc;
+use wasm_bindgen::{JsValue, UnwrapThrowExt};
+
+use crate::de::{take_js_ref_options, Options};
+use crate::{from_value, Deserializer, Result};
+
+/// Newtype name that makes the [`Deserializer`] hand its options over to [`JsRef`].
+pub(crate) const JS_REF_MAGIC: &str = "c2f7d0a1-3e5b-4f86-9a1d-6b8e4c0f2d57";
+
+/// A field type that keeps the original [`JsValue`] and converts it into `T` only on demand.
+///
+/// Deserializing a `JsRef<T>` with [`Deserializer`](crate::Deserializer) just holds on to the
+/// JS value via [`preserve`](crate::preserve), so large sections of the input that are rarely
+/// needed don't have to be converted up front. The first call to [`JsRef::get`] converts the
+/// value with the options of the [`Deserializer`](crate::Deserializer) it was read with, or with
+/// the default ones if it was created from a [`JsValue`], and caches the result.
+///
+/// When serialized, a `JsRef<T>` produces the original JS value unless it was modified via
+/// [`JsRef::get_mut`] or created from a Rust value with [`JsRef::new`], in which case the
+/// cached `T` is serialized instead.
+///
+/// Like [`preserve`](crate::preserve), this only works with this crate's
+/// [`Serializer`](crate::Serializer) and [`Deserializer`](crate::Deserializer).
+#[derive(Clone, Debug)]
+pub struct JsRef<T> {
+    value: JsValue,
+    options: Option<Rc<Options>>,
+    cached: OnceCell<T>,
+    modified: bool,
+}
+
+impl<T> JsRef<T> {
+    /// Creates a `JsRef` holding the given Rust value, which is serialized as-is.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: JsValue::UNDEFINED,
+            options: None,
+            cached: OnceCell::from(value),
+            modified: true,
+        }
+    }
+
+    /// Returns the original JS value.
+    ///
+    /// This doesn't reflect any changes made via [`JsRef::get_mut`].
+    pub const fn as_js(&self) -> &JsValue {
+        &self.value
+    }
+
+    /// Returns `true` if the JS value has already been converted into `T`.
+    pub fn is_converted(&self) -> bool {
+        self.cached.get().is_some()
+    }
+}
+
+impl<T: DeserializeOwned> JsRef<T> {
+    /// Converts the JS value into `T` on first access, and returns the cached result afterwards.
+    ///
+    /// If the conversion fails, the error is returned and the next call tries again.
+    pub fn get(&self) -> Result<&T> {
+        if let Some(value) = self.cached.get() {
+            return Ok(value);
+        }
+        let value = Self::convert(self.value.clone(), self.options.clone())?;
+        Ok(self.cached.get_or_init(|| value))
+    }
+
+    /// Like [`JsRef::get`], but returns a mutable reference.
+    ///
+    /// After this is called, the `JsRef` is serialized from the cached `T`
+    /// instead of the original JS value.
+    pub fn get_mut(&mut self) -> Result<&mut T> {
+        self.get()?;
+        self.modified = true;
+        Ok(self.cached.get_mut().unwrap_throw())
+    }
+
+    /// Consumes the `JsRef` and returns the converted value.
+    pub fn into_inner(self) -> Result<T> {
+        match self.cached.into_inner() {
+            Some(value) => Ok(value),
+            None => Self::convert(self.value, self.options),
+        }
+    }
+
+    fn convert(value: JsValue, options: Option<Rc<Options>>) -> Result<T> {
+        match options {
+            Some(options) => T::deserialize(Deserializer::with_options(value, options)),
+            None => from_value(value),
+        }
+    }
+}
+
+impl<T> From<JsValue> for JsRef<T> {
+    fn from(value: JsValue) -> Self {
+        Self {
+            value,
+            options: None,
+            cached: OnceCell::new(),
+            modified: false,
+        }
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for JsRef<T> {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.cached.get() {
+            Some(value) if self.modified => value.serialize(ser),
+            _ => crate::preserve::serialize(&self.value, ser),
+        }
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for JsRef<T> {
+    fn deserialize<D: de::Deserializer<'de>>(de: D) -> std::result::Result<Self, D::Error> {
+        struct JsRefVisitor;
+
+        impl<'de> de::Visitor<'de> for JsRefVisitor {
+            type Value = (JsValue, Option<Rc<Options>>);
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("any JS value")
+            }
+
+            fn visit_newtype_struct<D: de::Deserializer<'de>>(
+                self,
+                de: D,
+            ) -> std::result::Result<Self::Value, D::Error> {
+                // Take the options before propagating errors, so they don't leak into another `JsRef`.
+                let value = crate::preserve::deserialize::<D, JsValue>(de);
+                let options = take_js_ref_options();
+                Ok((value?, options))
+            }
+        }
+
+        let (value, options) = de.deserialize_newtype_struct(JS_REF_MAGIC, JsRefVisitor)?;
+        Ok(Self {
+            options,
+            ..Self::from(value)
+        })
+    }
+}
@@ -7,40 +7,96 @@ use wasm_bindgen::prelude::*;
 
 mod de;
 mod error;
+mod js_ref;
+mod mask;
+pub mod patch;
+mod rename;
+pub mod rest;
 mod ser;
+mod update;
+mod value;
 
 pub use de::Deserializer;
 pub use error::Error;
+pub use js_ref::JsRef;
+pub use mask::FieldMask;
+pub use rename::RenameRule;
 pub use ser::Serializer;
+pub use value::Value;
 
 type Result<T> = std::result::Result<T, Error>;
 
-fn static_str_to_js(s: &'static str) -> JsString {
-    use std::cell::RefCell;
-    use std::collections::HashMap;
+/// Representation of enums that don't have any Serde tagging attributes.
+///
+/// Serde calls these enums externally tagged, but it's not always possible to annotate
+/// third-party types with `#[serde(tag = "...")]`, so this allows to change their representation
+/// in [`Serializer`] and [`Deserializer`] at runtime instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnumRepresentation {
+    /// `"Variant"` for unit variants and `{ Variant: ...payload... }` for all others,
+    /// compatible with `serde_json`.
+    #[default]
+    ExternallyTagged,
+    /// `{ [tag]: "Variant", ...fields... }`, like with `#[serde(tag = "...")]`.
+    ///
+    /// Tuple variants, newtype variants that don't contain an object, and variants
+    /// with a field named like the tag can't be represented this way.
+    InternallyTagged {
+        /// Name of the property holding the variant name.
+        tag: &'static str,
+    },
+    /// `{ [tag]: "Variant", [content]: ...payload... }`, like with
+    /// `#[serde(tag = "...", content = "...")]`.
+    AdjacentlyTagged {
+        /// Name of the property holding the variant name.
+        tag: &'static str,
+        /// Name of the property holding the variant payload.
+        content: &'static str,
+    },
+}
 
-    #[derive(Default)]
-    struct PtrHasher {
-        addr: usize,
-    }
+#[derive(Default)]
+struct PtrHasher {
+    addr: usize,
+}
 
-    impl std::hash::Hasher for PtrHasher {
-        fn write(&mut self, _bytes: &[u8]) {
-            unreachable!();
-        }
+impl std::hash::Hasher for PtrHasher {
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!();
+    }
 
-        fn write_usize(&mut self, addr_or_len: usize) {
-            if self.addr == 0 {
-                self.addr = addr_or_len;
-            }
+    fn write_usize(&mut self, addr_or_len: usize) {
+        if self.addr == 0 {
+            self.addr = addr_or_len;
         }
+    }
 
-        fn finish(&self) -> u64 {
-            self.addr as _
-        }
+    fn finish(&self) -> u64 {
+        self.addr as _
     }
+}
+
+/// Hasher for maps keyed by `&'static str` pointers, see [`static_str_to_js`].
+type PtrBuildHasher = std::hash::BuildHasherDefault<PtrHasher>;
+
+/// How [`Serializer`] handles NaN and infinite floats, which can't be represented in JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// Serialize them as JavaScript `NaN`, `Infinity` and `-Infinity` numbers.
+    #[default]
+    Preserve,
+    /// Serialize them as `null`, just like `JSON.stringify` would.
+    Null,
+    /// Serialize them as `"NaN"`, `"Infinity"` and `"-Infinity"` strings,
+    /// which [`Deserializer`] accepts back for floats.
+    String,
+    /// Fail serialization.
+    Error,
+}
 
-    type PtrBuildHasher = std::hash::BuildHasherDefault<PtrHasher>;
+fn static_str_to_js(s: &'static str) -> JsString {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
 
     thread_local! {
         // Since we're mainly optimising for converting the exact same string literal over and over again,
@@ -60,6 +116,7 @@ fn static_str_to_js(s: &'static str) -> JsString {
 /// Custom bindings to avoid using fallible `Reflect` for plain objects.
 #[wasm_bindgen]
 extern "C" {
+    #[derive(Clone)]
     type ObjectExt;
 
     #[wasm_bindgen(method, indexing_getter)]
@@ -74,11 +131,28 @@ pub fn from_value<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T> {
     T::deserialize(Deserializer::from(value))
 }
 
+/// Converts the value at the given [JSON Pointer] inside a [`JsValue`] into a Rust type,
+/// without converting the rest of it. See [`Deserializer::pointer`].
+///
+/// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+pub fn from_value_at<T: serde::de::DeserializeOwned>(value: JsValue, path: &str) -> Result<T> {
+    T::deserialize(Deserializer::from(value).pointer(path)?)
+}
+
 /// Converts a Rust value into a [`JsValue`].
 pub fn to_value<T: serde::ser::Serialize + ?Sized>(value: &T) -> Result<JsValue> {
     value.serialize(&Serializer::new())
 }
 
+/// Updates an existing JS object, array or `Map` in place to match a Rust value, writing
+/// only what changed. See [`Serializer::serialize_into`].
+pub fn to_value_into<T: serde::ser::Serialize + ?Sized>(
+    value: &T,
+    existing: &JsValue,
+) -> Result<Vec<String>> {
+    Serializer::new().serialize_into(value, existing)
+}
+
 /// Serialization and deserialization functions that pass JavaScript objects through unchanged.
 ///
 /// This module is compatible with the `serde(with)` annotation, so for example if you create
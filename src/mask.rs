@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A nested selection of struct fields for [`Serializer::projection`](crate::Serializer::projection).
+///
+/// Masks are built from dot-separated paths such as `author.name`, where each segment is
+/// a property name as it appears in the output, i.e. after
+/// [`Serializer::rename_all`](crate::Serializer::rename_all) is applied. Sequences, maps,
+/// options and enum variants are transparent, so `comments.text` selects the `text` field
+/// of every element of a `comments: Vec<Comment>` field.
+///
+/// Selecting a field without any nested paths selects all of its contents.
+///
+/// Structs with `#[serde(flatten)]` fields are passed by Serde as maps, so masks don't apply
+/// to them, and they are always serialized in full.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldMask(Option<Rc<HashMap<String, FieldMask>>>);
+
+/// Selects all fields, like the default [`Serializer`](crate::Serializer) does.
+impl Default for FieldMask {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl FieldMask {
+    /// Creates a mask that doesn't select any fields yet.
+    pub fn none() -> Self {
+        Self(Some(Rc::default()))
+    }
+
+    /// Creates a mask that selects all fields.
+    pub const fn all() -> Self {
+        Self(None)
+    }
+
+    /// Creates a mask that selects the given dot-separated paths.
+    pub fn from_paths<I: IntoIterator<Item = S>, S: AsRef<str>>(paths: I) -> Self {
+        paths
+            .into_iter()
+            .fold(Self::none(), |mask, path| mask.with_path(path.as_ref()))
+    }
+
+    /// Adds a dot-separated path to the selection.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.insert(&path.split('.').collect::<Vec<_>>());
+        self
+    }
+
+    fn insert(&mut self, path: &[&str]) {
+        let Some((first, rest)) = path.split_first() else {
+            *self = Self::all();
+            return;
+        };
+        if let Some(fields) = &mut self.0 {
+            Rc::make_mut(fields)
+                .entry((*first).to_owned())
+                .or_insert_with(Self::none)
+                .insert(rest);
+        }
+    }
+
+    pub(crate) const fn is_all(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the mask for the contents of the given field, or `None` if it's not selected.
+    pub(crate) fn field(&self, name: &str) -> Option<Self> {
+        match &self.0 {
+            Some(fields) => fields.get(name).cloned(),
+            None => Some(Self::all()),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902) operations between JS values.
+//!
+//! [`diff`] serializes two Rust values and returns a JS array of `add`, `remove` and `replace`
+//! operations that turn the first one into the second one, for example
+//!
+//! ```js
+//! [
+//!   { op: "replace", path: "/title", value: "New title" },
+//!   { op: "add", path: "/items/2", value: { id: 3 } },
+//!   { op: "remove", path: "/tags/0" },
+//! ]
+//! ```
+//!
+//! Values in the operations are encoded by the [`Serializer`], so use [`Serializer::diff`]
+//! to produce them with custom options. Nested `Map`s are diffed entry by entry as long as
+//! their keys are strings or numbers, which are looked up by their string representation.
+//! `Map`s with different keys that share a string representation, such as `"1"` and `1`,
+//! are replaced as a whole.
+//!
+//! [`apply`] applies such operations to any JS value, so the patch can be consumed by
+//! JS code that doesn't know about Rust types at all.
+
+use js_sys::{Array, JsString, Map, Object};
+use serde::ser::Serialize;
+use std::collections::hash_map::{Entry, HashMap};
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+
+use crate::de::{find_map_key, parse_array_index, parse_pointer, resolve};
+use crate::update::{is_plain_object, is_same_class, join, map_key_segment, same_bytes};
+use crate::{static_str_to_js, Error, ObjectExt, Result, Serializer};
+
+/// Serializes both values and returns the JSON Patch operations that turn `old` into `new`.
+pub fn diff<T: ?Sized + Serialize>(old: &T, new: &T) -> Result<Array> {
+    Serializer::new().diff(old, new)
+}
+
+/// Applies JSON Patch operations to a JS value, mutating it in place.
+///
+/// Only `add`, `remove` and `replace` operations are supported. Returns the resulting value,
+/// which is a different one only if the patch replaces the whole `target`.
+pub fn apply(target: JsValue, patch: &Array) -> Result<JsValue> {
+    patch
+        .iter()
+        .try_fold(target, |target, op| apply_op(target, &op))
+}
+
+fn push_op(patch: &Array, op: &'static str, path: &str, value: Option<JsValue>) {
+    let object = Object::new();
+    let target = object.unchecked_ref::<ObjectExt>();
+    target.set(static_str_to_js("op"), static_str_to_js(op).into());
+    target.set(static_str_to_js("path"), path.into());
+    if let Some(value) = value {
+        target.set(static_str_to_js("value"), value);
+    }
+    patch.push(&object);
+}
+
+/// Checks whether the keys of both `Map`s can be addressed by pointer segments.
+///
+/// New keys are added as strings, so those have to be strings already. Different keys
+/// with the same segment, such as `"1"` and `1`, can't be told apart either.
+fn has_addressable_keys(old: &Map, new: &Map) -> bool {
+    let mut segments = HashMap::<String, JsValue>::new();
+    let mut is_unique = |key: &JsValue| match segments.entry(map_key_segment(key)) {
+        Entry::Occupied(entry) => Object::is(entry.get(), key),
+        Entry::Vacant(entry) => {
+            entry.insert(key.clone());
+            true
+        }
+    };
+    let mut ok = true;
+    old.for_each(&mut |_, key| {
+        ok &= is_unique(&key) && (key.is_string() || key.as_f64().is_some());
+    });
+    new.for_each(&mut |_, key| {
+        ok &= is_unique(&key) && (key.is_string() || (key.as_f64().is_some() && old.has(&key)));
+    });
+    ok
+}
+
+/// Appends operations that turn `old` into `new` to the `patch`.
+pub(crate) fn diff_values(
+    old: &JsValue,
+    new: &JsValue,
+    path: &str,
+    patch: &Array,
+    serializer: &Serializer,
+) {
+    if Object::is(old, new) || same_bytes(old, new) {
+        return;
+    }
+    if let (Some(old), Some(new)) = (old.dyn_ref::<Array>(), new.dyn_ref::<Array>()) {
+        let common = old.length().min(new.length());
+        for i in 0..common {
+            diff_values(&old.get(i), &new.get(i), &join(path, i), patch, serializer);
+        }
+        for i in common..new.length() {
+            push_op(patch, "add", &join(path, i), Some(new.get(i)));
+        }
+        // Remove from the end, so that indices of the remaining elements don't shift.
+        for i in (common..old.length()).rev() {
+            push_op(patch, "remove", &join(path, i), None);
+        }
+    } else if let (Some(old), Some(new)) = (old.dyn_ref::<Map>(), new.dyn_ref::<Map>())
+        && has_addressable_keys(old, new)
+    {
+        new.for_each(&mut |value, key| {
+            let path = join(path, map_key_segment(&key));
+            if old.has(&key) {
+                diff_values(&old.get(&key), &value, &path, patch, serializer);
+            } else {
+                push_op(patch, "add", &path, Some(value));
+            }
+        });
+        old.for_each(&mut |_, key| {
+            if !new.has(&key) {
+                push_op(patch, "remove", &join(path, map_key_segment(&key)), None);
+            }
+        });
+    } else if (is_plain_object(old) && is_plain_object(new)) || is_same_class(new, old, serializer)
+    {
+        for entry in Object::entries(new.unchecked_ref()).iter() {
+            let entry = entry.unchecked_into::<Array>();
+            let key = entry.get(0);
+            let path = join(path, key.as_string().unwrap_throw());
+            if Object::has_own(old.unchecked_ref::<Object>(), &key) {
+                let old_value = old
+                    .unchecked_ref::<ObjectExt>()
+                    .get_with_ref_key(key.unchecked_ref());
+                diff_values(&old_value, &entry.get(1), &path, patch, serializer);
+            } else {
+                push_op(patch, "add", &path, Some(entry.get(1)));
+            }
+        }
+        for key in Object::keys(old.unchecked_ref::<Object>()).iter() {
+            if !Object::has_own(new.unchecked_ref::<Object>(), &key) {
+                push_op(
+                    patch,
+                    "remove",
+                    &join(path, key.as_string().unwrap_throw()),
+                    None,
+                );
+            }
+        }
+    } else {
+        push_op(patch, "replace", path, Some(new.clone()));
+    }
+}
+
+fn get_string(op: &JsValue, key: &'static str) -> Result<String> {
+    op.unchecked_ref::<ObjectExt>()
+        .get_with_ref_key(&static_str_to_js(key))
+        .as_string()
+        .ok_or_else(|| Error::new(format_args!("patch operation is missing a `{key}` string")))
+}
+
+fn apply_op(target: JsValue, op: &JsValue) -> Result<JsValue> {
+    if !op.is_object() {
+        return Err(Error::new("patch operation must be an object"));
+    }
+    let kind = get_string(op, "op")?;
+    let path = get_string(op, "path")?;
+    let value = match kind.as_str() {
+        "add" | "replace" => {
+            let key = static_str_to_js("value");
+            if !key.js_in(op) {
+                return Err(Error::new(format_args!(
+                    "`{kind}` operation at `{path}` is missing a `value`"
+                )));
+            }
+            Some(op.unchecked_ref::<ObjectExt>().get_with_ref_key(&key))
+        }
+        "remove" => None,
+        _ => {
+            return Err(Error::new(format_args!(
+                "unsupported patch operation `{kind}`"
+            )))
+        }
+    };
+    let segments = parse_pointer(&path)?;
+    let Some((last, parents)) = segments.split_last() else {
+        return value.ok_or_else(|| Error::new("can't remove the root value"));
+    };
+    let parent = resolve(target.clone(), parents)?;
+    apply_to_parent(&parent, last, value, kind == "add").map_err(|err| {
+        segments
+            .iter()
+            .rev()
+            .fold(err, |err, segment| err.at(segment))
+    })?;
+    Ok(target)
+}
+
+/// Adds, replaces or removes (if `value` is `None`) the child of `parent` at `segment`.
+fn apply_to_parent(
+    parent: &JsValue,
+    segment: &str,
+    value: Option<JsValue>,
+    insert: bool,
+) -> Result<()> {
+    if let Some(array) = parent.dyn_ref::<Array>() {
+        let len = array.length();
+        let index = if insert && segment == "-" {
+            len
+        } else {
+            parse_array_index(segment)
+                .ok_or_else(|| Error::new(format_args!("invalid array index `{segment}`")))?
+        };
+        if index > len || (!insert && index == len) {
+            return Err(Error::new(format_args!(
+                "array index {index} is out of bounds for length {len}"
+            )));
+        }
+        match value {
+            Some(value) if insert => {
+                array.splice(index, 0, &value);
+            }
+            Some(value) => array.set(index, value),
+            None => {
+                array.copy_within(index as i32, index as i32 + 1, len as i32);
+                array.set_length(len - 1);
+            }
+        }
+        return Ok(());
+    }
+    if let Some(map) = parent.dyn_ref::<Map>() {
+        let key = find_map_key(map, segment);
+        match (key, value) {
+            (Some(key), Some(value)) => {
+                map.set(&key, &value);
+            }
+            (None, Some(value)) if insert => {
+                map.set(&segment.into(), &value);
+            }
+            (Some(key), None) => {
+                map.delete(&key);
+            }
+            (None, _) => return Err(Error::new(format_args!("missing Map entry `{segment}`"))),
+        }
+        return Ok(());
+    }
+    if parent.is_object() {
+        let key = JsString::from(segment);
+        if !insert && !Object::has_own(parent.unchecked_ref::<Object>(), &key) {
+            return Err(Error::new(format_args!("missing property `{segment}`")));
+        }
+        match value {
+            Some(value) => parent.unchecked_ref::<ObjectExt>().set(key, value),
+            None => {
+                js_sys::Reflect::delete_property(parent.unchecked_ref::<Object>(), &key)?;
+            }
+        }
+        return Ok(());
+    }
+    Err(Error::new(format_args!(
+        "can't apply a patch operation at `{segment}` to a non-container value"
+    )))
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use js_sys::JsString;
+
+use crate::{static_str_to_js, PtrBuildHasher};
+
+/// Case convention to apply to struct field names and enum variant names at runtime.
+///
+/// This mirrors `#[serde(rename_all = "...")]` for types that can't be annotated directly,
+/// for example because they come from third-party crates. Like in Serde, field names are
+/// assumed to be in `snake_case` and variant names in `PascalCase` originally.
+///
+/// Serde doesn't tell apart names that were explicitly changed with `#[serde(rename = "...")]`,
+/// so names that don't follow the original convention, such as `"fieldName"` for a field or
+/// `"variant-name"` for a variant, are assumed to be renamed and kept as they are.
+///
+/// Structs with any `#[serde(flatten)]` field are passed by Serde as maps rather than structs,
+/// so none of their own fields are renamed either, only the fields of nested structs that
+/// aren't flattened themselves.
+///
+/// Serde buffers values of flattened fields and untagged or internally tagged enums and
+/// deserializes them on its own, without this rule, so the [`Deserializer`](crate::Deserializer)
+/// fails on objects in such values instead of silently ignoring renamed fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RenameRule {
+    /// Keep names as they are.
+    #[default]
+    None,
+    /// `camelCase`, for example `fieldName` and `variantName`.
+    CamelCase,
+    /// `PascalCase`, for example `FieldName` and `VariantName`.
+    PascalCase,
+    /// `kebab-case`, for example `field-name` and `variant-name`.
+    KebabCase,
+    /// `SCREAMING_SNAKE_CASE`, for example `FIELD_NAME` and `VARIANT_NAME`.
+    ScreamingSnakeCase,
+}
+
+/// Whether a name comes from a struct field or an enum variant.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum NameKind {
+    Field,
+    Variant,
+}
+
+impl RenameRule {
+    /// Converts a `snake_case` field name into a [`JsString`] according to this rule.
+    pub(crate) fn field_to_js(self, field: &'static str) -> JsString {
+        self.name_to_js(field, NameKind::Field)
+    }
+
+    /// Converts a `PascalCase` variant name into a [`JsString`] according to this rule.
+    pub(crate) fn variant_to_js(self, variant: &'static str) -> JsString {
+        self.name_to_js(variant, NameKind::Variant)
+    }
+
+    /// Converts a `snake_case` field name according to this rule.
+    pub(crate) fn field_name(self, field: &'static str) -> &'static str {
+        type Cache = HashMap<RenameRule, HashMap<*const str, &'static str, PtrBuildHasher>>;
+
+        thread_local! {
+            // Field names are static strings, so there's only a bounded number of converted
+            // ones, and it's fine to leak them in exchange for not allocating on every call.
+            static CACHE: RefCell<Cache> = Default::default();
+        }
+
+        if self == Self::None {
+            return field;
+        }
+        CACHE.with(|cache| {
+            *cache
+                .borrow_mut()
+                .entry(self)
+                .or_default()
+                .entry(field)
+                .or_insert_with(|| Box::leak(self.apply(field, NameKind::Field).into_boxed_str()))
+        })
+    }
+
+    fn name_to_js(self, name: &'static str, kind: NameKind) -> JsString {
+        type Cache = HashMap<(RenameRule, NameKind), HashMap<*const str, JsString, PtrBuildHasher>>;
+
+        thread_local! {
+            // Same as in `static_str_to_js`, but separately for each rule and kind of name.
+            static CACHE: RefCell<Cache> = Default::default();
+        }
+
+        if self == Self::None {
+            return static_str_to_js(name);
+        }
+        CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .entry((self, kind))
+                .or_default()
+                .entry(name)
+                .or_insert_with(|| self.apply(name, kind).as_str().into())
+                .clone()
+        })
+    }
+
+    fn apply(self, name: &str, kind: NameKind) -> String {
+        let is_original = match kind {
+            NameKind::Field => name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'),
+            NameKind::Variant => {
+                name.starts_with(|ch: char| ch.is_uppercase())
+                    && name.chars().all(char::is_alphanumeric)
+            }
+        };
+        if !is_original {
+            return name.to_owned();
+        }
+
+        // Split the name into lowercase words first, then join them in the target case.
+        let mut words = Vec::new();
+        match kind {
+            NameKind::Field => words.extend(name.split('_').map(str::to_owned)),
+            NameKind::Variant => {
+                for (i, ch) in name.char_indices() {
+                    if i == 0 || ch.is_uppercase() {
+                        words.push(String::new());
+                    }
+                    words.last_mut().unwrap().extend(ch.to_lowercase());
+                }
+            }
+        }
+
+        match self {
+            Self::None => name.to_owned(),
+            Self::CamelCase | Self::PascalCase => {
+                let mut result = String::with_capacity(name.len());
+                for word in &words {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        if result.is_empty() && self == Self::CamelCase {
+                            result.push(first);
+                        } else {
+                            result.extend(first.to_uppercase());
+                        }
+                        result.push_str(chars.as_str());
+                    }
+                }
+                result
+            }
+            Self::KebabCase => words.join("-"),
+            Self::ScreamingSnakeCase => words.join("_").to_uppercase(),
+        }
+    }
+}
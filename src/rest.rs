@@ -0,0 +1,212 @@
+//! Serialization and deserialization functions that capture unknown properties into a JS object.
+//!
+//! This module is meant to be used together with `#[serde(flatten)]`, so for example if you
+//! create the struct
+//!
+//! ```rust
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct MyStruct {
+//!     known_field: i32,
+//!     #[serde(flatten, with = "serde_wasm_bindgen::rest")]
+//!     rest: js_sys::Object,
+//! }
+//! ```
+//!
+//! then `serde_wasm_bindgen::from_value` will deserialize `known_field` as usual, and put all
+//! the other properties of the source object into a fresh `rest` object, holding the original
+//! JS values without converting them. `serde_wasm_bindgen::to_value` will merge those properties
+//! back into the resulting object.
+//!
+//! Property values that don't fit into the Serde data model, such as `Set`s, functions or typed
+//! arrays, are captured as well.
+//!
+//! Note that the properties are looked up on the object that is currently being deserialized,
+//! so this doesn't work for structs that Serde has to buffer before deserializing: those nested
+//! in internally tagged or untagged enums, or in fields collected by another `#[serde(flatten)]`
+//! struct. In such cases the properties are checked against the data buffered by Serde, and
+//! deserialization fails with an error instead of picking up unrelated values of the outer object.
+
+use js_sys::{Array, JsString, Map, Object};
+use serde::de::{self, DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess};
+use serde::ser::{self, SerializeMap};
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+
+use crate::de::{current_map_source, FieldSource};
+use crate::ObjectExt;
+
+struct Preserved(JsValue);
+
+impl ser::Serialize for Preserved {
+    fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        crate::preserve::serialize(&self.0, ser)
+    }
+}
+
+/// Serialize properties of the given object into the parent struct.
+///
+/// When used with the `Serializer` in `serde_wasm_bindgen`, property values are passed
+/// through as `JsValue`s.
+///
+/// This function is compatible with the `serde(serialize_with)` derive annotation.
+pub fn serialize<S: ser::Serializer>(rest: &Object, ser: S) -> Result<S::Ok, S::Error> {
+    // Passing keys as `str` like Serde does for struct fields makes `serde_wasm_bindgen::Serializer`
+    // produce a plain object even if this is used without `#[serde(flatten)]`.
+    let mut map = ser.serialize_map(None)?;
+    for entry in Object::entries(rest).iter() {
+        let entry = entry.unchecked_into::<Array>();
+        let key = entry.get(0).as_string().unwrap_throw();
+        map.serialize_entry(key.as_str(), &Preserved(entry.get(1)))?;
+    }
+    map.end()
+}
+
+/// Checks whether a value buffered by Serde could have been produced from the given JS value.
+///
+/// Values that don't fit into the Serde data model are buffered as `()`, and `Map`s are only
+/// compared by size, since their keys can be of any type.
+struct BufferedFrom<'a>(Option<&'a JsValue>);
+
+impl<'de> DeserializeSeed<'de> for BufferedFrom<'_> {
+    type Value = bool;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, de: D) -> Result<bool, D::Error> {
+        de.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for BufferedFrom<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a value buffered from a JS value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| value.as_bool() == Some(v)))
+    }
+
+    // Integers are buffered both from numbers and `BigInt`s, which are loosely equal in JS.
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| value.loose_eq(&v.into())))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| value.loose_eq(&v.into())))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| value.loose_eq(&v.into())))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| value.loose_eq(&v.into())))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<bool, E> {
+        Ok(self
+            .0
+            .and_then(JsValue::as_f64)
+            .is_some_and(|value| value == v || (value.is_nan() && v.is_nan())))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+        Ok(self.0.and_then(JsValue::as_string).as_deref() == Some(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, _v: &[u8]) -> Result<bool, E> {
+        Ok(self.0.is_some_and(JsValue::is_object))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+        Ok(self.0.is_some_and(|value| {
+            value.as_bool().is_none() && value.as_f64().is_none() && !value.is_string()
+        }))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<bool, A::Error> {
+        let array = self.0.and_then(|value| value.dyn_ref::<Array>());
+        let mut matches = array.is_some();
+        let mut index = 0;
+        loop {
+            let item = array.map(|array| array.get(index));
+            match seq.next_element_seed(BufferedFrom(item.as_ref()))? {
+                Some(item_matches) => matches &= item_matches,
+                None => break,
+            }
+            index += 1;
+        }
+        Ok(matches && array.is_some_and(|array| array.length() == index))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        if let Some(js_map) = self.0.and_then(|value| value.dyn_ref::<Map>()) {
+            let mut size = 0;
+            while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+                size += 1;
+            }
+            return Ok(js_map.size() == size);
+        }
+        let object = self
+            .0
+            .filter(|value| value.is_object())
+            .map(|value| FieldSource::Object(value.clone().unchecked_into()));
+        let mut matches = object.is_some();
+        while let Some(key) = map.next_key::<String>()? {
+            let key = JsString::from(key);
+            let value = object
+                .as_ref()
+                .filter(|object| object.has_own(&key))
+                .and_then(|object| object.get(&key));
+            matches &= map.next_value_seed(BufferedFrom(value.as_ref()))?;
+        }
+        Ok(matches)
+    }
+}
+
+/// Deserialize remaining properties of the parent struct into a fresh JS object.
+///
+/// This only works with the `Deserializer` in `serde_wasm_bindgen`, and fails for any others.
+///
+/// This function is compatible with the `serde(deserialize_with)` derive annotation.
+pub fn deserialize<'de, D: de::Deserializer<'de>>(de: D) -> Result<Object, D::Error> {
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+        type Value = Object;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("remaining properties of a JS object")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let source = current_map_source().ok_or_else(|| {
+                A::Error::custom("serde_wasm_bindgen::rest can only be used with serde_wasm_bindgen::Deserializer")
+            })?;
+            let rest = Object::new();
+            while let Some(key) = map.next_key::<String>()? {
+                let js_key = JsString::from(key.as_str());
+                // Buffered structs are visited while an outer object is still the current
+                // source, so make sure the property actually belongs to it by comparing
+                // its value with the one buffered by Serde.
+                let value = source
+                    .has_own(&js_key)
+                    .then(|| source.get(&js_key).unwrap_throw());
+                let matches = map.next_value_seed(BufferedFrom(value.as_ref()))?;
+                let value = match value {
+                    Some(value) if matches => value,
+                    _ => {
+                        return Err(A::Error::custom(format_args!(
+                            "property `{key}` is not found on the object being deserialized; \
+                             serde_wasm_bindgen::rest can't be used in structs that Serde buffers, \
+                             such as those in enums or in fields of flattened structs"
+                        )))
+                    }
+                };
+                rest.unchecked_ref::<ObjectExt>().set(js_key, value);
+            }
+            Ok(rest)
+        }
+    }
+
+    de.deserialize_map(Visitor)
+}
@@ -1,33 +1,102 @@
 use js_sys::{Array, JsString, Map, Number, Object, Uint8Array};
 use serde::ser::{self, Error as _, Serialize};
+use std::collections::HashMap;
+use std::rc::Rc;
 use wasm_bindgen::convert::RefFromWasmAbi;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 use crate::preserve::PRESERVED_VALUE_MAGIC;
-use crate::{static_str_to_js, Error, ObjectExt};
+use crate::value::{serializing_value, VALUE_MAGIC};
+use crate::{
+    static_str_to_js, EnumRepresentation, Error, FieldMask, NonFiniteFloats, ObjectExt, RenameRule,
+};
 
 type Result<T = JsValue> = super::Result<T>;
 
+/// Adds the location of a variant payload within the tagged object to an error
+/// if `ensure_json_safe` is enabled.
+fn payload_error(
+    representation: EnumRepresentation,
+    ensure_json_safe: bool,
+    variant: &JsString,
+    error: Error,
+) -> Error {
+    if !ensure_json_safe {
+        return error;
+    }
+    match representation {
+        EnumRepresentation::ExternallyTagged => error.at(variant),
+        EnumRepresentation::InternallyTagged { .. } => error,
+        EnumRepresentation::AdjacentlyTagged { content, .. } => error.at(content),
+    }
+}
+
+/// Wraps a serialized variant payload into the configured [`EnumRepresentation`].
+///
+/// For internally tagged variants, the payload must be a plain object or nullish,
+/// which is checked by the callers, and must not have a property named like the tag.
+fn tag_variant(representation: EnumRepresentation, variant: JsString, payload: JsValue) -> Result {
+    let obj = Object::new().unchecked_into::<ObjectExt>();
+    match representation {
+        EnumRepresentation::ExternallyTagged => {
+            obj.set(variant, payload);
+        }
+        EnumRepresentation::InternallyTagged { tag } => {
+            if payload.is_object() {
+                let payload = payload.unchecked_ref::<Object>();
+                if Object::has_own(payload, &static_str_to_js(tag)) {
+                    return Err(tag_collision(&variant, tag));
+                }
+                Object::assign(obj.unchecked_ref::<Object>(), payload);
+            }
+            obj.set(static_str_to_js(tag), variant.into());
+        }
+        EnumRepresentation::AdjacentlyTagged { tag, content } => {
+            obj.set(static_str_to_js(tag), variant.into());
+            obj.set(static_str_to_js(content), payload);
+        }
+    }
+    Ok(obj.into())
+}
+
+#[cold]
+fn tag_collision(variant: &JsString, tag: &str) -> Error {
+    Error::custom(format_args!(
+        "cannot serialize internally tagged variant {variant} with a field named like its tag `{tag}`"
+    ))
+}
+
 /// Wraps other serializers into an enum tagged variant form.
 ///
-/// Results in `{"Variant": ...payload...}` for compatibility with serde-json.
+/// Results in `{"Variant": ...payload...}` for compatibility with serde-json,
+/// unless a different [`EnumRepresentation`] is configured.
 pub struct VariantSerializer<S> {
-    variant: &'static str,
+    representation: EnumRepresentation,
+    rename_all: RenameRule,
+    ensure_json_safe: bool,
+    variant: JsString,
     inner: S,
 }
 
 impl<S> VariantSerializer<S> {
-    const fn new(variant: &'static str, inner: S) -> Self {
-        Self { variant, inner }
+    const fn new(serializer: &Serializer, variant: JsString, inner: S) -> Self {
+        Self {
+            representation: serializer.enum_representation,
+            rename_all: serializer.rename_all,
+            ensure_json_safe: serializer.ensure_json_safe,
+            variant,
+            inner,
+        }
     }
 
     fn end(self, inner: impl FnOnce(S) -> Result) -> Result {
         let value = inner(self.inner)?;
-        let obj = Object::new();
-        obj.unchecked_ref::<ObjectExt>()
-            .set(static_str_to_js(self.variant), value);
-        Ok(obj.into())
+        match self.representation {
+            // Internally tagged struct variants get their tag upfront, see `serialize_struct_variant`.
+            EnumRepresentation::InternallyTagged { .. } => Ok(value),
+            representation => tag_variant(representation, self.variant, value),
+        }
     }
 }
 
@@ -38,7 +107,14 @@ impl<S: ser::SerializeTupleStruct<Ok = JsValue, Error = Error>> ser::SerializeTu
     type Error = Error;
 
     fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        self.inner.serialize_field(value)
+        self.inner.serialize_field(value).map_err(|err| {
+            payload_error(
+                self.representation,
+                self.ensure_json_safe,
+                &self.variant,
+                err,
+            )
+        })
     }
 
     fn end(self) -> Result {
@@ -57,7 +133,20 @@ impl<S: ser::SerializeStruct<Ok = JsValue, Error = Error>> ser::SerializeStructV
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        self.inner.serialize_field(key, value)
+        // The tag of internally tagged struct variants is already set, see `serialize_struct_variant`.
+        if let EnumRepresentation::InternallyTagged { tag } = self.representation
+            && self.rename_all.field_name(key) == tag
+        {
+            return Err(tag_collision(&self.variant, tag));
+        }
+        self.inner.serialize_field(key, value).map_err(|err| {
+            payload_error(
+                self.representation,
+                self.ensure_json_safe,
+                &self.variant,
+                err,
+            )
+        })
     }
 
     fn end(self) -> Result {
@@ -87,7 +176,10 @@ impl ser::SerializeSeq for ArraySerializer<'_> {
     type Error = Error;
 
     fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        self.target.set(self.idx, value.serialize(self.serializer)?);
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|err| self.serializer.error_at(err, self.idx))?;
+        self.target.set(self.idx, value);
         self.idx += 1;
         Ok(())
     }
@@ -123,32 +215,263 @@ impl ser::SerializeTupleStruct for ArraySerializer<'_> {
     }
 }
 
+/// Serializes map keys into JS strings so that they can be used as object keys.
+///
+/// Follows `serde_json` in stringifying integer, boolean, char and unit variant keys,
+/// and rejects everything else.
+///
+/// Unit variants are named the same way as in values, according to the options
+/// of the wrapped [`Serializer`].
+struct ObjectKeySerializer<'s>(&'s Serializer);
+
+#[cold]
+fn key_must_be_a_string() -> Error {
+    Error::custom("Map key is not a string and cannot be an object key")
+}
+
+macro_rules! stringify_key {
+    ($($name:ident($ty:ty);)*) => {
+        $(fn $name(self, v: $ty) -> Result<JsString> {
+            Ok(v.to_string().into())
+        })*
+    };
+}
+
+macro_rules! reject_key {
+    ($($name:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(fn $name(self, $(_: $arg),*) -> Result<$ret> {
+            Err(key_must_be_a_string())
+        })*
+    };
+}
+
+impl ser::Serializer for ObjectKeySerializer<'_> {
+    type Ok = JsString;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<JsString, Error>;
+    type SerializeTuple = ser::Impossible<JsString, Error>;
+    type SerializeTupleStruct = ser::Impossible<JsString, Error>;
+    type SerializeTupleVariant = ser::Impossible<JsString, Error>;
+    type SerializeMap = ser::Impossible<JsString, Error>;
+    type SerializeStruct = ser::Impossible<JsString, Error>;
+    type SerializeStructVariant = ser::Impossible<JsString, Error>;
+
+    stringify_key! {
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+    }
+
+    reject_key! {
+        serialize_f32(f32) -> JsString;
+        serialize_f64(f64) -> JsString;
+        serialize_bytes(&[u8]) -> JsString;
+        serialize_none() -> JsString;
+        serialize_unit() -> JsString;
+        serialize_unit_struct(&'static str) -> JsString;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<JsString> {
+        Ok(static_str_to_js(if v { "true" } else { "false" }))
+    }
+
+    fn serialize_char(self, v: char) -> Result<JsString> {
+        Ok(JsString::from(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JsString> {
+        Ok(JsString::from(v))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<JsString> {
+        if self.0.serialize_unit_variants_as_numbers {
+            return self.serialize_u32(variant_index);
+        }
+        Ok(self.0.rename_all.variant_to_js(variant))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<JsString> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<JsString> {
+        if name == PRESERVED_VALUE_MAGIC {
+            return Serializer::new()
+                .serialize_newtype_struct(name, value)?
+                .dyn_into::<JsString>()
+                .map_err(|_| key_must_be_a_string());
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<JsString> {
+        Err(key_must_be_a_string())
+    }
+}
+
 pub enum MapResult {
     Map(Map),
     Object(Object),
 }
 
+/// Tracks keys of a map that is being serialized as a plain object for as long as possible.
+struct ObjectKeyTracker {
+    preserve_order: bool,
+    last_index: Option<u32>,
+    has_non_index_keys: bool,
+}
+
+impl ObjectKeyTracker {
+    const fn new(preserve_order: bool) -> Self {
+        Self {
+            preserve_order,
+            last_index: None,
+            has_non_index_keys: false,
+        }
+    }
+
+    /// Checks whether the given key can be added to a plain object without changing
+    /// its meaning or its position among the existing keys.
+    fn accepts(&mut self, key: &JsValue) -> bool {
+        let key = match key.as_string() {
+            Some(key) => key,
+            None => return false,
+        };
+        // Assigning `__proto__` would change the prototype instead of adding a property.
+        if key == "__proto__" {
+            return false;
+        }
+        if !self.preserve_order {
+            return true;
+        }
+        match as_array_index(&key) {
+            // JS objects always enumerate integer-like keys first and in ascending order,
+            // so we can only add one if it doesn't need to be moved before existing keys.
+            Some(index) => {
+                if self.has_non_index_keys || self.last_index.is_some_and(|last| last >= index) {
+                    return false;
+                }
+                self.last_index = Some(index);
+            }
+            None => self.has_non_index_keys = true,
+        }
+        true
+    }
+}
+
+/// Parses a string that JS engines treat as an integer index for ordering purposes.
+fn as_array_index(key: &str) -> Option<u32> {
+    if key.is_empty() || (key.len() > 1 && key.starts_with('0')) {
+        return None;
+    }
+    if !key.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    key.parse::<u32>().ok().filter(|&index| index != u32::MAX)
+}
+
 /// Serializes Rust maps into JS `Map` or plain JS objects.
 ///
 /// Plain JS objects are used if `serialize_maps_as_objects` is set to `true`,
-/// but then only string keys are supported.
+/// in which case integer, boolean, char and unit variant keys are converted to strings
+/// the same way `serde_json` does, and other key types are rejected.
+///
+/// If `serialize_string_keyed_maps_as_objects` is set to `true` instead, maps start as
+/// plain JS objects and are transparently converted to a `Map` as soon as a key is found
+/// that is not a string or would be reordered by the object semantics.
+///
+/// Structs with `#[serde(flatten)]` fields are serialized by Serde as maps of unknown length,
+/// with their field names passed as `str` keys. Such maps are collected into a `Map` first,
+/// and converted to a plain JS object at the end if a field name key was seen and all keys
+/// turned out to be strings.
 pub struct MapSerializer<'s> {
     serializer: &'s Serializer,
     target: MapResult,
+    tracker: Option<ObjectKeyTracker>,
+    /// Whether a field name key was seen, for maps that might be flattened structs.
+    flattened: Option<bool>,
     next_key: Option<JsValue>,
 }
 
+/// Checks whether a map key was passed as an unsized `str`, which is how Serde derives
+/// pass field names of flattened structs. Real maps pass references to their sized keys.
+const fn is_field_name<T: ?Sized>() -> bool {
+    std::mem::size_of::<&T>() != std::mem::size_of::<&()>()
+}
+
 impl<'s> MapSerializer<'s> {
-    pub fn new(serializer: &'s Serializer, as_object: bool) -> Self {
-        Self {
-            serializer,
-            target: if as_object {
-                MapResult::Object(Object::new())
+    fn new(serializer: &'s Serializer, len: Option<usize>) -> Result<Self> {
+        let mut flattened = None;
+        let (target, tracker) = if serializer.serialize_maps_as_objects {
+            (MapResult::Object(Object::new()), None)
+        } else if serializer.serialize_string_keyed_maps_as_objects {
+            (
+                MapResult::Object(Object::new()),
+                Some(ObjectKeyTracker::new(true)),
+            )
+        } else {
+            if len.is_none() {
+                // Might be a flattened struct, which is checked at the end.
+                flattened = Some(false);
             } else {
-                MapResult::Map(Map::new())
-            },
+                serializer.check_json_safe("ES2015 Map")?;
+            }
+            (MapResult::Map(Map::new()), None)
+        };
+        Ok(Self {
+            serializer,
+            target,
+            tracker,
+            flattened,
             next_key: None,
+        })
+    }
+
+    /// Moves entries collected so far into a new `Map` and continues with it.
+    fn switch_to_map(&mut self) -> Result<()> {
+        self.serializer
+            .check_json_safe("Map with non-string or reordered keys")?;
+        if let MapResult::Object(object) = &self.target {
+            let map = Map::new();
+            for entry in Object::entries(object).iter() {
+                let entry = entry.unchecked_into::<Array>();
+                map.set(&entry.get(0), &entry.get(1));
+            }
+            self.target = MapResult::Map(map);
         }
+        self.tracker = None;
+        Ok(())
     }
 }
 
@@ -158,22 +481,42 @@ impl ser::SerializeMap for MapSerializer<'_> {
 
     fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
         debug_assert!(self.next_key.is_none());
-        self.next_key = Some(key.serialize(self.serializer)?);
+        let key = match (&self.target, &mut self.tracker) {
+            (MapResult::Object(_), None) => {
+                key.serialize(ObjectKeySerializer(self.serializer))?.into()
+            }
+            (MapResult::Object(_), Some(tracker)) => {
+                let key = key.serialize(self.serializer)?;
+                if !tracker.accepts(&key) {
+                    self.switch_to_map()?;
+                }
+                key
+            }
+            (MapResult::Map(_), _) => {
+                if let Some(flattened) = &mut self.flattened {
+                    *flattened |= is_field_name::<T>();
+                }
+                key.serialize(self.serializer)?
+            }
+        };
+        self.next_key = Some(key);
         Ok(())
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
         let key = self.next_key.take().unwrap_throw();
-        let value_ser = value.serialize(self.serializer)?;
+        let value_ser = value.serialize(self.serializer).map_err(|err| {
+            self.serializer
+                .error_at(err, crate::update::map_key_segment(&key))
+        })?;
         match &self.target {
             MapResult::Map(map) => {
                 map.set(&key, &value_ser);
             }
             MapResult::Object(object) => {
-                let key = key.dyn_into::<JsString>().map_err(|_| {
-                    Error::custom("Map key is not a string and cannot be an object key")
-                })?;
-                object.unchecked_ref::<ObjectExt>().set(key, value_ser);
+                object
+                    .unchecked_ref::<ObjectExt>()
+                    .set(key.unchecked_into(), value_ser);
             }
         }
         Ok(())
@@ -182,12 +525,122 @@ impl ser::SerializeMap for MapSerializer<'_> {
     fn end(self) -> Result {
         debug_assert!(self.next_key.is_none());
         match self.target {
-            MapResult::Map(map) => Ok(map.into()),
+            MapResult::Map(map) => {
+                if let Some(flattened) = self.flattened {
+                    // Order of struct fields doesn't matter, so flattened structs
+                    // only need string keys to become plain objects.
+                    if flattened
+                        && map
+                            .keys()
+                            .into_iter()
+                            .all(|key| key.is_ok_and(|key| key.is_string()))
+                    {
+                        return Ok(Object::from_entries(&map)?.into());
+                    }
+                    self.serializer.check_json_safe(if flattened {
+                        "Map with non-string keys"
+                    } else {
+                        "ES2015 Map"
+                    })?;
+                }
+                Ok(map.into())
+            }
             MapResult::Object(object) => Ok(object.into()),
         }
     }
 }
 
+/// Serializes struct field values with the wrapped [`Serializer`].
+///
+/// The only difference is that `None` is allowed to become `undefined` with
+/// `ensure_json_safe`, since `JSON.stringify` omits such fields and they
+/// are read back as `None`.
+struct FieldValueSerializer<'s>(&'s Serializer);
+
+macro_rules! forward_to_serializer {
+    ($($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(fn $name(self, $($arg: $ty),*) -> Result<$ret> {
+            ser::Serializer::$name(self.0, $($arg),*)
+        })*
+    };
+}
+
+impl<'s> ser::Serializer for FieldValueSerializer<'s> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    type SerializeSeq = ArraySerializer<'s>;
+    type SerializeTuple = ArraySerializer<'s>;
+    type SerializeTupleStruct = ArraySerializer<'s>;
+    type SerializeTupleVariant = VariantSerializer<ArraySerializer<'s>>;
+    type SerializeMap = MapSerializer<'s>;
+    type SerializeStruct = StructSerializer<'s>;
+    type SerializeStructVariant = VariantSerializer<StructSerializer<'s>>;
+
+    forward_to_serializer! {
+        serialize_bool(v: bool) -> JsValue;
+        serialize_i8(v: i8) -> JsValue;
+        serialize_i16(v: i16) -> JsValue;
+        serialize_i32(v: i32) -> JsValue;
+        serialize_i64(v: i64) -> JsValue;
+        serialize_i128(v: i128) -> JsValue;
+        serialize_u8(v: u8) -> JsValue;
+        serialize_u16(v: u16) -> JsValue;
+        serialize_u32(v: u32) -> JsValue;
+        serialize_u64(v: u64) -> JsValue;
+        serialize_u128(v: u128) -> JsValue;
+        serialize_f32(v: f32) -> JsValue;
+        serialize_f64(v: f64) -> JsValue;
+        serialize_char(v: char) -> JsValue;
+        serialize_str(v: &str) -> JsValue;
+        serialize_bytes(v: &[u8]) -> JsValue;
+        serialize_unit() -> JsValue;
+        serialize_unit_struct(name: &'static str) -> JsValue;
+        serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str) -> JsValue;
+        serialize_seq(len: Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(len: usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(name: &'static str, len: usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Self::SerializeTupleVariant;
+        serialize_map(len: Option<usize>) -> Self::SerializeMap;
+        serialize_struct(name: &'static str, len: usize) -> Self::SerializeStruct;
+        serialize_struct_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_none(self) -> Result {
+        Ok(if self.0.serialize_missing_as_null {
+            JsValue::NULL
+        } else {
+            JsValue::UNDEFINED
+        })
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result {
+        value.serialize(self.0)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result {
+        ser::Serializer::serialize_newtype_struct(self.0, name, value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result {
+        ser::Serializer::serialize_newtype_variant(self.0, name, variant_index, variant, value)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.human_readable
+    }
+}
+
 /// Serializes Rust structs into plain JS objects.
 pub struct ObjectSerializer<'s> {
     serializer: &'s Serializer,
@@ -201,6 +654,14 @@ impl<'s> ObjectSerializer<'s> {
             target: Object::new().unchecked_into::<ObjectExt>(),
         }
     }
+
+    /// Creates a serializer that writes fields into a new instance of the given prototype.
+    fn with_prototype(serializer: &'s Serializer, prototype: &Object) -> Self {
+        Self {
+            serializer,
+            target: Object::create(prototype).unchecked_into::<ObjectExt>(),
+        }
+    }
 }
 
 impl ser::SerializeStruct for ObjectSerializer<'_> {
@@ -212,8 +673,12 @@ impl ser::SerializeStruct for ObjectSerializer<'_> {
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        let value = value.serialize(self.serializer)?;
-        self.target.set(static_str_to_js(key), value);
+        let Some(value) = self.serializer.serialize_field_value(key, value) else {
+            return Ok(());
+        };
+        let key = self.serializer.rename_all.field_to_js(key);
+        let value = value.map_err(|err| self.serializer.error_at(err, &key))?;
+        self.target.set(key, value);
         Ok(())
     }
 
@@ -222,13 +687,85 @@ impl ser::SerializeStruct for ObjectSerializer<'_> {
     }
 }
 
+/// Serializes Rust structs into plain JS objects, or into positional JS arrays
+/// if `serialize_structs_as_arrays` is set.
+pub enum StructSerializer<'s> {
+    Object(ObjectSerializer<'s>),
+    Array(ArraySerializer<'s>),
+}
+
+impl ser::SerializeStruct for StructSerializer<'_> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        match self {
+            Self::Object(object) => object.serialize_field(key, value),
+            Self::Array(array) => {
+                // Fields skipped by the projection still take their position.
+                let value = match array.serializer.serialize_field_value(key, value) {
+                    Some(value) => {
+                        value.map_err(|err| array.serializer.error_at(err, array.idx))?
+                    }
+                    None => JsValue::UNDEFINED,
+                };
+                array.target.set(array.idx, value);
+                array.idx += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Fields skipped via `#[serde(skip_serializing_if = "...")]` keep their position
+    /// in arrays as `undefined`.
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        if let Self::Array(array) = self {
+            array.target.set(array.idx, JsValue::UNDEFINED);
+            array.idx += 1;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result {
+        match self {
+            Self::Object(object) => ser::SerializeStruct::end(object),
+            Self::Array(array) => ser::SerializeSeq::end(array),
+        }
+    }
+}
+
 /// A [`serde::Serializer`] that converts supported Rust values into a [`JsValue`].
-#[derive(Default)]
 pub struct Serializer {
     serialize_missing_as_null: bool,
     serialize_maps_as_objects: bool,
+    serialize_string_keyed_maps_as_objects: bool,
     serialize_large_number_types_as_bigints: bool,
+    serialize_unsafe_integers_as_bigints: bool,
+    serialize_large_number_types_as_strings: bool,
+    serialize_unsafe_integers_as_strings: bool,
+    serialize_128_bit_integers_as_strings: bool,
     serialize_bytes_as_arrays: bool,
+    serialize_unit_variants_as_numbers: bool,
+    serialize_f32_as_shortest_decimal: bool,
+    serialize_structs_as_arrays: bool,
+    non_finite_floats: NonFiniteFloats,
+    ensure_json_safe: bool,
+    enum_representation: EnumRepresentation,
+    rename_all: RenameRule,
+    human_readable: bool,
+    type_tag: Option<&'static str>,
+    projection: FieldMask,
+    classes: Option<Rc<HashMap<&'static str, Object>>>,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Serializer {
@@ -237,8 +774,24 @@ impl Serializer {
         Self {
             serialize_missing_as_null: false,
             serialize_maps_as_objects: false,
+            serialize_string_keyed_maps_as_objects: false,
             serialize_large_number_types_as_bigints: false,
+            serialize_unsafe_integers_as_bigints: false,
+            serialize_large_number_types_as_strings: false,
+            serialize_unsafe_integers_as_strings: false,
+            serialize_128_bit_integers_as_strings: false,
             serialize_bytes_as_arrays: false,
+            serialize_unit_variants_as_numbers: false,
+            serialize_f32_as_shortest_decimal: false,
+            serialize_structs_as_arrays: false,
+            non_finite_floats: NonFiniteFloats::Preserve,
+            ensure_json_safe: false,
+            enum_representation: EnumRepresentation::ExternallyTagged,
+            rename_all: RenameRule::None,
+            human_readable: true,
+            type_tag: None,
+            projection: FieldMask::all(),
+            classes: None,
         }
     }
 
@@ -246,12 +799,32 @@ impl Serializer {
     /// uses plain objects instead of ES maps. So you will get the same result of
     /// `JsValue::from_serde`, and you can stringify results to JSON and store
     /// it without data loss.
+    ///
+    /// 128-bit integers are serialized as decimal strings, since `BigInt`s can't be
+    /// stringified to JSON. Enable `serialize_unsafe_integers_as_strings` to do the same
+    /// for 64-bit integers outside of the safe integer range.
     pub const fn json_compatible() -> Self {
         Self {
             serialize_missing_as_null: true,
             serialize_maps_as_objects: true,
+            serialize_string_keyed_maps_as_objects: false,
             serialize_large_number_types_as_bigints: false,
+            serialize_unsafe_integers_as_bigints: false,
+            serialize_large_number_types_as_strings: false,
+            serialize_unsafe_integers_as_strings: false,
+            serialize_128_bit_integers_as_strings: true,
             serialize_bytes_as_arrays: true,
+            serialize_unit_variants_as_numbers: false,
+            serialize_f32_as_shortest_decimal: false,
+            serialize_structs_as_arrays: false,
+            non_finite_floats: NonFiniteFloats::Preserve,
+            ensure_json_safe: false,
+            enum_representation: EnumRepresentation::ExternallyTagged,
+            rename_all: RenameRule::None,
+            human_readable: true,
+            type_tag: None,
+            projection: FieldMask::all(),
+            classes: None,
         }
     }
 
@@ -263,12 +836,23 @@ impl Serializer {
     }
 
     /// Set to `true` to serialize maps into plain JavaScript objects instead of
-    /// ES2015 `Map`s. `false` by default.
+    /// ES2015 `Map`s. Non-string keys are stringified like in `serde_json`.
+    /// `false` by default.
     pub const fn serialize_maps_as_objects(mut self, value: bool) -> Self {
         self.serialize_maps_as_objects = value;
         self
     }
 
+    /// Set to `true` to serialize maps into plain JavaScript objects when all their keys
+    /// are strings, and into ES2015 `Map`s otherwise. Maps with integer-like string keys
+    /// that JavaScript objects would reorder are kept as `Map`s too, so the original
+    /// order is always preserved. Has no effect if `serialize_maps_as_objects` is set.
+    /// `false` by default.
+    pub const fn serialize_string_keyed_maps_as_objects(mut self, value: bool) -> Self {
+        self.serialize_string_keyed_maps_as_objects = value;
+        self
+    }
+
     /// Set to `true` to serialize 64-bit numbers to JavaScript `BigInt` instead of
     /// plain numbers. `false` by default.
     pub const fn serialize_large_number_types_as_bigints(mut self, value: bool) -> Self {
@@ -276,12 +860,342 @@ impl Serializer {
         self
     }
 
+    /// Set to `true` to serialize 64-bit numbers to plain numbers when they are within
+    /// the safe integer range, and to JavaScript `BigInt` otherwise. Has no effect if
+    /// `serialize_large_number_types_as_bigints` is set. `false` by default.
+    pub const fn serialize_unsafe_integers_as_bigints(mut self, value: bool) -> Self {
+        self.serialize_unsafe_integers_as_bigints = value;
+        self
+    }
+
+    /// Set to `true` to serialize 64-bit and 128-bit numbers to decimal strings
+    /// instead of plain numbers or `BigInt`s. Takes precedence over other options
+    /// for large number types. `false` by default.
+    pub const fn serialize_large_number_types_as_strings(mut self, value: bool) -> Self {
+        self.serialize_large_number_types_as_strings = value;
+        self
+    }
+
+    /// Set to `true` to serialize 64-bit numbers to plain numbers when they are within
+    /// the safe integer range, and to decimal strings otherwise. 128-bit numbers are always
+    /// serialized as decimal strings in this mode. Takes precedence over `BigInt` options.
+    /// `false` by default.
+    pub const fn serialize_unsafe_integers_as_strings(mut self, value: bool) -> Self {
+        self.serialize_unsafe_integers_as_strings = value;
+        self
+    }
+
+    /// Set to `true` to serialize 128-bit numbers to decimal strings instead of `BigInt`s.
+    /// `false` by default, but enabled in [`Serializer::json_compatible`].
+    pub const fn serialize_128_bit_integers_as_strings(mut self, value: bool) -> Self {
+        self.serialize_128_bit_integers_as_strings = value;
+        self
+    }
+
     /// Set to `true` to serialize bytes into plain JavaScript arrays instead of
     /// ES2015 `Uint8Array`s. `false` by default.
     pub const fn serialize_bytes_as_arrays(mut self, value: bool) -> Self {
         self.serialize_bytes_as_arrays = value;
         self
     }
+
+    /// Set to `true` to serialize unit variants as their numeric indices
+    /// (useful for TypeScript numeric enums) instead of variant names.
+    /// `false` by default.
+    pub const fn serialize_unit_variants_as_numbers(mut self, value: bool) -> Self {
+        self.serialize_unit_variants_as_numbers = value;
+        self
+    }
+
+    /// Set to `true` to serialize `f32` as the shortest decimal number that converts back
+    /// to the same `f32`, so that e.g. `0.1f32` becomes `0.1` instead of `0.10000000149011612`.
+    /// `false` by default.
+    pub const fn serialize_f32_as_shortest_decimal(mut self, value: bool) -> Self {
+        self.serialize_f32_as_shortest_decimal = value;
+        self
+    }
+
+    /// Set to `true` to serialize structs and struct variants into arrays of field values
+    /// in declaration order instead of objects. This is much more compact for large numbers
+    /// of records, and [`Deserializer`](crate::Deserializer) accepts such arrays for structs.
+    /// `false` by default.
+    pub const fn serialize_structs_as_arrays(mut self, value: bool) -> Self {
+        self.serialize_structs_as_arrays = value;
+        self
+    }
+
+    /// Set how NaN and infinite floats are serialized.
+    /// [`NonFiniteFloats::Preserve`] by default.
+    pub const fn non_finite_floats(mut self, value: NonFiniteFloats) -> Self {
+        self.non_finite_floats = value;
+        self
+    }
+
+    /// Set to `true` to guarantee that the result can be passed to `JSON.stringify`
+    /// without throwing or losing any data. `false` by default.
+    ///
+    /// In this mode, serialization fails with an error pointing to the offending location
+    /// instead of producing `undefined`, `-0`, `BigInt`s, non-finite numbers, `Uint8Array`s,
+    /// ES2015 `Map`s or preserved JS values that aren't plain JSON data.
+    /// `None` struct fields are still allowed to be `undefined`, since they are omitted
+    /// by `JSON.stringify` and read back as `None`.
+    ///
+    /// Errors in other modes don't carry the location.
+    /// This is best combined with [`Serializer::json_compatible`], which avoids
+    /// producing most of these in the first place.
+    pub const fn ensure_json_safe(mut self, value: bool) -> Self {
+        self.ensure_json_safe = value;
+        self
+    }
+
+    /// Set how enums without any Serde tagging attributes are represented.
+    /// [`EnumRepresentation::ExternallyTagged`] by default.
+    pub const fn enum_representation(mut self, value: EnumRepresentation) -> Self {
+        self.enum_representation = value;
+        self
+    }
+
+    /// Set the case convention to convert struct field names and enum variant names to.
+    /// [`RenameRule::None`] by default.
+    pub const fn rename_all(mut self, value: RenameRule) -> Self {
+        self.rename_all = value;
+        self
+    }
+
+    /// Set the value returned from [`ser::Serializer::is_human_readable`]. `true` by default.
+    ///
+    /// Set to `false` to let types like `IpAddr` or `SocketAddr` use their compact
+    /// representation instead of strings. Values produced this way should be read back
+    /// with [`Deserializer::human_readable`](crate::Deserializer::human_readable) set to `false`.
+    pub const fn human_readable(mut self, value: bool) -> Self {
+        self.human_readable = value;
+        self
+    }
+
+    /// Set a property name to record the Rust type name of serialized structs under,
+    /// for example `Some("__type")`. `None` by default.
+    ///
+    /// Structs are tagged with their name, like `{ __type: "Point", x: 1, y: 2 }`, struct
+    /// variants with `"Enum::Variant"`, and unit structs become `{ __type: "Unit" }`
+    /// instead of `undefined` or `null`. Newtype structs are transparent, so they carry
+    /// the tag of their contents, if any. Structs serialized as arrays aren't tagged.
+    ///
+    /// Use [`Deserializer::type_tag`](crate::Deserializer::type_tag) with the same name
+    /// to check the tags when reading the values back.
+    pub const fn type_tag(mut self, key: Option<&'static str>) -> Self {
+        self.type_tag = key;
+        self
+    }
+
+    /// Only serialize the struct fields selected by the given [`FieldMask`], skipping
+    /// all the work for the others. All fields are serialized by default.
+    ///
+    /// Structs with any `#[serde(flatten)]` field are passed by Serde as maps, so the
+    /// projection doesn't apply to their own fields, and they are serialized in full.
+    pub fn projection(mut self, mask: FieldMask) -> Self {
+        self.projection = mask;
+        self
+    }
+
+    /// Serialize structs and struct variants with the given name into instances of a JS class
+    /// instead of plain objects, so that they have access to its methods.
+    ///
+    /// The `name` is the one passed to [`ser::Serializer::serialize_struct`], which is the
+    /// struct name or, for struct variants, the variant name. Fields are assigned directly to
+    /// an object created from `constructor.prototype`, without calling the constructor.
+    /// [`Deserializer`](crate::Deserializer) reads such instances like any other object.
+    ///
+    /// [`Serializer::serialize_into`] and [`Serializer::diff`] update existing instances of the
+    /// same class field by field. With `ensure_json_safe`, class instances are rejected, since
+    /// they lose their prototype in JSON.
+    ///
+    /// Fails if the constructor doesn't have a `prototype` object, as is the case for arrow
+    /// functions and methods.
+    pub fn class(self, name: &'static str, constructor: &js_sys::Function) -> Result<Self> {
+        let prototype = js_sys::Reflect::get(constructor, &static_str_to_js("prototype"))?
+            .dyn_into::<Object>()
+            .map_err(|_| {
+                Error::custom(format_args!(
+                    "class constructor for `{name}` must have a `prototype` object"
+                ))
+            })?;
+        Ok(self.prototype(name, &prototype))
+    }
+
+    /// Like [`Serializer::class`], but takes the prototype object for instances directly.
+    pub fn prototype(mut self, name: &'static str, prototype: &Object) -> Self {
+        Rc::make_mut(self.classes.get_or_insert_with(Default::default))
+            .insert(name, prototype.clone());
+        self
+    }
+
+    /// Checks whether the value is an instance of one of the registered classes.
+    pub(crate) fn is_class_instance(&self, value: &JsValue) -> bool {
+        let Some(classes) = &self.classes else {
+            return false;
+        };
+        if !value.is_object() {
+            return false;
+        }
+        let prototype = Object::get_prototype_of(value);
+        classes.values().any(|class| Object::is(class, &prototype))
+    }
+
+    /// Serializes the value of a struct field, or returns `None` if it's not selected
+    /// by the projection.
+    fn serialize_field_value<T: ?Sized + Serialize>(
+        &self,
+        key: &'static str,
+        value: &T,
+    ) -> Option<Result> {
+        if self.projection.is_all() {
+            return Some(value.serialize(FieldValueSerializer(self)));
+        }
+        let mask = self.projection.field(self.rename_all.field_name(key))?;
+        Some(value.serialize(FieldValueSerializer(&self.with_projection(mask))))
+    }
+
+    fn with_projection(&self, projection: FieldMask) -> Self {
+        Self {
+            projection,
+            classes: self.classes.clone(),
+            ..*self
+        }
+    }
+
+    /// Serializes `value` and updates the `existing` JS object, array or `Map` in place to match,
+    /// instead of returning a fresh one. Returns [JSON Pointer]s of all properties, array elements
+    /// and `Map` entries that were written or deleted.
+    ///
+    /// Nested objects, arrays and `Map`s that are already present are updated recursively,
+    /// so unchanged ones keep their identity and no writes happen for unchanged values.
+    /// This plays well with UI frameworks that track object identity and property writes.
+    ///
+    /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub fn serialize_into<T: ?Sized + Serialize>(
+        &self,
+        value: &T,
+        existing: &JsValue,
+    ) -> Result<Vec<String>> {
+        let fresh = value.serialize(self)?;
+        crate::update::reconcile_root(existing, &fresh, self)
+    }
+
+    /// Serializes both values and returns [JSON Patch] operations that turn `old` into `new`.
+    /// See the [`patch`](crate::patch) module for details.
+    ///
+    /// [JSON Patch]: https://datatracker.ietf.org/doc/html/rfc6902
+    pub fn diff<T: ?Sized + Serialize>(&self, old: &T, new: &T) -> Result<Array> {
+        let old = old.serialize(self)?;
+        let new = new.serialize(self)?;
+        let patch = Array::new();
+        crate::patch::diff_values(&old, &new, "", &patch, self);
+        Ok(patch)
+    }
+}
+
+#[cold]
+fn not_json_safe(what: impl std::fmt::Display) -> Error {
+    Error::custom(format_args!("{what} can't be represented in JSON"))
+}
+
+/// Checks that a JS value passed through as-is consists only of JSON data.
+fn check_json_value(value: &JsValue) -> Result<()> {
+    if value.is_null() || value.as_bool().is_some() || value.is_string() {
+        return Ok(());
+    }
+    if let Some(v) = value.as_f64() {
+        return if v.is_finite() && !(v == 0.0 && v.is_sign_negative()) {
+            Ok(())
+        } else {
+            Err(not_json_safe(v))
+        };
+    }
+    if let Some(array) = value.dyn_ref::<Array>() {
+        for (i, item) in array.iter().enumerate() {
+            check_json_value(&item).map_err(|err| err.at(i))?;
+        }
+        return Ok(());
+    }
+    if let Some(object) = value.dyn_ref::<Object>() {
+        // Only plain objects are safe, others might have a custom `toJSON`
+        // or lose their type information.
+        let proto = Object::get_prototype_of(object);
+        if proto.is_null() || Object::get_prototype_of(&proto).is_null() {
+            for entry in Object::entries(object).iter() {
+                let entry = entry.unchecked_into::<Array>();
+                check_json_value(&entry.get(1))
+                    .map_err(|err| err.at(entry.get(0).as_string().unwrap_throw()))?;
+            }
+            return Ok(());
+        }
+        return Err(not_json_safe(format_args!(
+            "{} object",
+            String::from(object.constructor().name())
+        )));
+    }
+    Err(not_json_safe(format_args!(
+        "Value of type {}",
+        value.js_typeof().as_string().unwrap_throw()
+    )))
+}
+
+impl Serializer {
+    /// Adds the location of a nested value to an error if `ensure_json_safe` is enabled.
+    fn error_at(&self, error: Error, segment: impl std::fmt::Display) -> Error {
+        if self.ensure_json_safe {
+            error.at(segment)
+        } else {
+            error
+        }
+    }
+
+    /// Fails with an error if `ensure_json_safe` is enabled.
+    fn check_json_safe(&self, what: impl std::fmt::Display) -> Result<()> {
+        if self.ensure_json_safe {
+            Err(not_json_safe(what))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Serializes a 64-bit or 128-bit integer according to the configured options.
+    ///
+    /// `safe` holds the integer converted to a JS number if it's within the safe integer range.
+    /// If no string options are set, the integer becomes a `BigInt` if `bigint` is `true`,
+    /// or if `unsafe_bigint` is `true` and it's outside the safe integer range.
+    fn serialize_large_integer<T: std::fmt::Display + Into<JsValue>>(
+        &self,
+        v: T,
+        safe: Option<f64>,
+        bigint: bool,
+        unsafe_bigint: bool,
+    ) -> Result {
+        if self.serialize_large_number_types_as_strings {
+            return Ok(JsString::from(v.to_string()).into());
+        }
+        if self.serialize_unsafe_integers_as_strings {
+            return Ok(match safe {
+                Some(safe) => safe.into(),
+                None => JsString::from(v.to_string()).into(),
+            });
+        }
+        if bigint {
+            self.check_json_safe("BigInt")?;
+            return Ok(v.into());
+        }
+        match safe {
+            Some(safe) => Ok(safe.into()),
+            None if unsafe_bigint => {
+                self.check_json_safe("BigInt")?;
+                Ok(v.into())
+            }
+            None => Err(Error::custom(format_args!(
+                "{} can't be represented as a JavaScript number",
+                v
+            ))),
+        }
+    }
 }
 
 macro_rules! forward_to_into {
@@ -301,8 +1215,8 @@ impl<'s> ser::Serializer for &'s Serializer {
     type SerializeTupleStruct = ArraySerializer<'s>;
     type SerializeTupleVariant = VariantSerializer<ArraySerializer<'s>>;
     type SerializeMap = MapSerializer<'s>;
-    type SerializeStruct = ObjectSerializer<'s>;
-    type SerializeStructVariant = VariantSerializer<ObjectSerializer<'s>>;
+    type SerializeStruct = StructSerializer<'s>;
+    type SerializeStructVariant = VariantSerializer<StructSerializer<'s>>;
 
     forward_to_into! {
         serialize_bool(bool);
@@ -315,65 +1229,110 @@ impl<'s> ser::Serializer for &'s Serializer {
         serialize_u16(u16);
         serialize_u32(u32);
 
-        serialize_f32(f32);
-        serialize_f64(f64);
-
         serialize_str(&str);
     }
 
-    /// Serializes `i64` into a `BigInt` or a JS number.
+    /// Serializes `f32` into a JS number.
     ///
-    /// If `serialize_large_number_types_as_bigints` is set to `false`,
-    /// `i64` is serialized as a JS number. But in this mode only numbers
-    /// within the safe integer range are supported.
-    fn serialize_i64(self, v: i64) -> Result {
-        if self.serialize_large_number_types_as_bigints {
+    /// If `serialize_f32_as_shortest_decimal` is set to `true`, the shortest decimal
+    /// that round-trips to the same `f32` is used instead of the exact value.
+    fn serialize_f32(self, v: f32) -> Result {
+        if self.serialize_f32_as_shortest_decimal && v.is_finite() {
+            // `Display` for floats already produces the shortest round-tripping representation.
+            let v = v.to_string().parse().unwrap_throw();
+            return self.serialize_f64(v);
+        }
+        self.serialize_f64(v.into())
+    }
+
+    /// Serializes `f64` into a JS number, or handles NaN and infinities
+    /// according to the `non_finite_floats` option.
+    fn serialize_f64(self, v: f64) -> Result {
+        if v.is_finite() {
+            // `JSON.stringify` turns negative zero into `0`.
+            if v == 0.0 && v.is_sign_negative() {
+                self.check_json_safe("-0")?;
+            }
             return Ok(v.into());
         }
+        match self.non_finite_floats {
+            NonFiniteFloats::Preserve => {
+                self.check_json_safe(v)?;
+                Ok(v.into())
+            }
+            NonFiniteFloats::Null => Ok(JsValue::NULL),
+            NonFiniteFloats::String => Ok(static_str_to_js(if v.is_nan() {
+                "NaN"
+            } else if v > 0.0 {
+                "Infinity"
+            } else {
+                "-Infinity"
+            })
+            .into()),
+            NonFiniteFloats::Error => Err(Error::custom(format_args!(
+                "{v} can't be represented as a finite number"
+            ))),
+        }
+    }
 
+    /// Serializes `i64` into a `BigInt`, a JS number or a decimal string.
+    ///
+    /// If `serialize_large_number_types_as_bigints` is set to `false`,
+    /// `i64` is serialized as a JS number. But in this mode only numbers
+    /// within the safe integer range are supported, unless
+    /// `serialize_unsafe_integers_as_bigints` or string options are set.
+    fn serialize_i64(self, v: i64) -> Result {
         // Note: don't try to "simplify" by using `.abs()` as it can overflow,
         // but range check can't.
         const MIN_SAFE_INTEGER: i64 = Number::MIN_SAFE_INTEGER as i64;
         const MAX_SAFE_INTEGER: i64 = Number::MAX_SAFE_INTEGER as i64;
 
-        if (MIN_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
-            self.serialize_f64(v as _)
-        } else {
-            Err(Error::custom(format_args!(
-                "{} can't be represented as a JavaScript number",
-                v
-            )))
-        }
+        let safe = (MIN_SAFE_INTEGER..=MAX_SAFE_INTEGER)
+            .contains(&v)
+            .then_some(v as f64);
+        self.serialize_large_integer(
+            v,
+            safe,
+            self.serialize_large_number_types_as_bigints,
+            self.serialize_unsafe_integers_as_bigints,
+        )
     }
 
-    /// Serializes `u64` into a `BigInt` or a JS number.
+    /// Serializes `u64` into a `BigInt`, a JS number or a decimal string.
     ///
     /// If `serialize_large_number_types_as_bigints` is set to `false`,
     /// `u64` is serialized as a JS number. But in this mode only numbers
-    /// within the safe integer range are supported.
+    /// within the safe integer range are supported, unless
+    /// `serialize_unsafe_integers_as_bigints` or string options are set.
     fn serialize_u64(self, v: u64) -> Result {
-        if self.serialize_large_number_types_as_bigints {
-            return Ok(v.into());
-        }
-
-        if v <= Number::MAX_SAFE_INTEGER as u64 {
-            self.serialize_f64(v as _)
-        } else {
-            Err(Error::custom(format_args!(
-                "{} can't be represented as a JavaScript number",
-                v
-            )))
-        }
+        let safe = (v <= Number::MAX_SAFE_INTEGER as u64).then_some(v as f64);
+        self.serialize_large_integer(
+            v,
+            safe,
+            self.serialize_large_number_types_as_bigints,
+            self.serialize_unsafe_integers_as_bigints,
+        )
     }
 
-    /// Serializes `i128` into a `BigInt`.
+    /// Serializes `i128` into a `BigInt` or a decimal string.
+    ///
+    /// 128-bit numbers are never serialized as plain JS numbers, since they are
+    /// only deserialized from those with `coerce_numbers_to_128_bit_integers`.
     fn serialize_i128(self, v: i128) -> Result {
-        Ok(JsValue::from(v))
+        if self.serialize_128_bit_integers_as_strings {
+            return Ok(JsString::from(v.to_string()).into());
+        }
+        self.serialize_large_integer(v, None, true, true)
     }
 
-    /// Serializes `u128` into a `BigInt`.
+    /// Serializes `u128` into a `BigInt` or a decimal string.
+    ///
+    /// See `serialize_i128` for details.
     fn serialize_u128(self, v: u128) -> Result {
-        Ok(JsValue::from(v))
+        if self.serialize_128_bit_integers_as_strings {
+            return Ok(JsString::from(v.to_string()).into());
+        }
+        self.serialize_large_integer(v, None, true, true)
     }
 
     /// Serializes `char` into a JS string.
@@ -393,6 +1352,7 @@ impl<'s> ser::Serializer for &'s Serializer {
         if self.serialize_bytes_as_arrays {
             Ok(JsValue::from(Array::from(view.as_ref())))
         } else {
+            self.check_json_safe("Uint8Array")?;
             Ok(JsValue::from(Uint8Array::new(view.as_ref())))
         }
     }
@@ -416,23 +1376,51 @@ impl<'s> ser::Serializer for &'s Serializer {
         Ok(if self.serialize_missing_as_null {
             JsValue::NULL
         } else {
+            self.check_json_safe("undefined")?;
             JsValue::UNDEFINED
         })
     }
 
-    /// Serializes unit structs into `undefined` or `null`.
-    fn serialize_unit_struct(self, _name: &'static str) -> Result {
-        self.serialize_unit()
+    /// Serializes unit structs into `undefined` or `null`, or into `{ [type_tag]: name }`
+    /// if `type_tag` is set.
+    fn serialize_unit_struct(self, name: &'static str) -> Result {
+        match self.type_tag {
+            Some(key) => {
+                let obj = Object::new().unchecked_into::<ObjectExt>();
+                obj.set(static_str_to_js(key), static_str_to_js(name).into());
+                Ok(obj.into())
+            }
+            None => self.serialize_unit(),
+        }
     }
 
     /// For compatibility with serde-json, serializes unit variants as "Variant" strings.
+    ///
+    /// If `serialize_unit_variants_as_numbers` is set to `true`, serializes them as
+    /// `variant_index` numbers instead.
+    ///
+    /// With internally or adjacently tagged [`EnumRepresentation`], serializes them
+    /// as `{ [tag]: "Variant" }` instead.
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
     ) -> Result {
-        Ok(static_str_to_js(variant).into())
+        let variant = if self.serialize_unit_variants_as_numbers {
+            variant_index.into()
+        } else {
+            self.rename_all.variant_to_js(variant).into()
+        };
+        match self.enum_representation {
+            EnumRepresentation::ExternallyTagged => Ok(variant),
+            EnumRepresentation::InternallyTagged { tag }
+            | EnumRepresentation::AdjacentlyTagged { tag, .. } => {
+                let obj = Object::new().unchecked_into::<ObjectExt>();
+                obj.set(static_str_to_js(tag), variant);
+                Ok(obj.into())
+            }
+        }
     }
 
     /// Serializes newtype structs as their inner values.
@@ -446,20 +1434,55 @@ impl<'s> ser::Serializer for &'s Serializer {
             // `PreservedValueSerWrapper` gives us ABI of a reference to a `JsValue` that is
             // guaranteed to be alive only during this call.
             // We must clone it before giving away the value to the caller.
-            return Ok(unsafe { JsValue::ref_from_abi(abi) }.as_ref().clone());
+            let value = unsafe { JsValue::ref_from_abi(abi) }.as_ref().clone();
+            if self.ensure_json_safe {
+                check_json_value(&value)?;
+            }
+            return Ok(value);
+        }
+        if name == VALUE_MAGIC
+            && let Some(value) = serializing_value(value)
+        {
+            let value = value.to_js();
+            if self.ensure_json_safe {
+                check_json_value(&value)?;
+            }
+            return Ok(value);
         }
         value.serialize(self)
     }
 
-    /// Serializes newtype variants as their inner values.
+    /// Serializes newtype variants as `{"Variant": ...payload...}`, or according to the
+    /// configured [`EnumRepresentation`].
     fn serialize_newtype_variant<T: ?Sized + Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> Result {
-        VariantSerializer::new(variant, self.serialize_newtype_struct(variant, value)?).end(Ok)
+        let variant_js = self.rename_all.variant_to_js(variant);
+        let payload = self
+            .serialize_newtype_struct(variant, value)
+            .map_err(|err| {
+                payload_error(
+                    self.enum_representation,
+                    self.ensure_json_safe,
+                    &variant_js,
+                    err,
+                )
+            })?;
+        if let EnumRepresentation::InternallyTagged { .. } = self.enum_representation {
+            let is_plain_object =
+                payload.is_object() && !Array::is_array(&payload) && !payload.has_type::<Map>();
+            // Nullish payloads would leave only the tag behind, which can't be read back.
+            if !is_plain_object {
+                return Err(Error::custom(format_args!(
+                    "cannot serialize internally tagged newtype variant {name}::{variant} containing a non-object value"
+                )));
+            }
+        }
+        tag_variant(self.enum_representation, variant_js, payload)
     }
 
     /// Serializes any Rust iterable as a JS Array.
@@ -482,16 +1505,23 @@ impl<'s> ser::Serializer for &'s Serializer {
         self.serialize_tuple(len)
     }
 
-    /// Serializes Rust tuple variants as `{"Variant": [ ...tuple... ]}`.
+    /// Serializes Rust tuple variants as `{"Variant": [ ...tuple... ]}`, or according to the
+    /// configured [`EnumRepresentation`].
     fn serialize_tuple_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
+        if let EnumRepresentation::InternallyTagged { .. } = self.enum_representation {
+            return Err(Error::custom(format_args!(
+                "cannot serialize tuple variant {name}::{variant} as internally tagged"
+            )));
+        }
         Ok(VariantSerializer::new(
-            variant,
+            self,
+            self.rename_all.variant_to_js(variant),
             self.serialize_tuple_struct(variant, len)?,
         ))
     }
@@ -499,26 +1529,69 @@ impl<'s> ser::Serializer for &'s Serializer {
     /// Serializes Rust maps into JS `Map` or plain JS objects.
     ///
     /// See [`MapSerializer`] for more details.
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(MapSerializer::new(self, self.serialize_maps_as_objects))
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        MapSerializer::new(self, len)
     }
 
-    /// Serializes Rust typed structs into plain JS objects.
-    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        Ok(ObjectSerializer::new(self))
+    /// Serializes Rust structs into plain JS objects, or into arrays of field values
+    /// in declaration order if `serialize_structs_as_arrays` is set.
+    ///
+    /// If a class is registered for `name`, objects are created as its instances.
+    /// If `type_tag` is set, objects start with a property holding the struct name.
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        if self.serialize_structs_as_arrays {
+            return Ok(StructSerializer::Array(ArraySerializer::new(self)));
+        }
+        let prototype = self.classes.as_ref().and_then(|classes| classes.get(name));
+        let object = match prototype {
+            Some(prototype) => {
+                // Class instances lose their prototype and might have a custom `toJSON`.
+                self.check_json_safe(format_args!("{name} class instance"))?;
+                ObjectSerializer::with_prototype(self, prototype)
+            }
+            None => ObjectSerializer::new(self),
+        };
+        if let Some(key) = self.type_tag {
+            object
+                .target
+                .set(static_str_to_js(key), static_str_to_js(name).into());
+        }
+        Ok(StructSerializer::Object(object))
     }
 
-    /// Serializes Rust struct-like variants into `{"Variant": { ...fields... }}`.
+    /// Serializes Rust struct-like variants into `{"Variant": { ...fields... }}`, or according
+    /// to the configured [`EnumRepresentation`].
     fn serialize_struct_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        Ok(VariantSerializer::new(
-            variant,
-            self.serialize_struct(variant, len)?,
-        ))
+        let variant_js = self.rename_all.variant_to_js(variant);
+        let inner = self.serialize_struct(variant, len)?;
+        if let (Some(key), StructSerializer::Object(object)) = (self.type_tag, &inner) {
+            object.target.set(
+                static_str_to_js(key),
+                JsString::from(format!("{name}::{variant}")).into(),
+            );
+        }
+        if let EnumRepresentation::InternallyTagged { tag } = self.enum_representation {
+            match &inner {
+                StructSerializer::Object(object) => object
+                    .target
+                    .set(static_str_to_js(tag), variant_js.clone().into()),
+                StructSerializer::Array(_) => {
+                    return Err(Error::custom(format_args!(
+                        "cannot serialize struct variant {name}::{variant} as an internally tagged array"
+                    )))
+                }
+            }
+        }
+        Ok(VariantSerializer::new(self, variant_js, inner))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
     }
 }
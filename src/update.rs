@@ -0,0 +1,197 @@
+//! Reconciliation of freshly serialized values with an existing JS object graph.
+
+use js_sys::{Array, Map, Object, Set, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{Error, ObjectExt, Result, Serializer};
+
+/// Appends an escaped [JSON Pointer](https://datatracker.ietf.org/doc/html/rfc6901) segment to `path`.
+pub(crate) fn join(path: &str, segment: impl std::fmt::Display) -> String {
+    let segment = segment.to_string().replace('~', "~0").replace('/', "~1");
+    format!("{path}/{segment}")
+}
+
+pub(crate) fn is_plain_object(value: &JsValue) -> bool {
+    match value.dyn_ref::<Object>() {
+        Some(object) => {
+            let proto = Object::get_prototype_of(object);
+            proto.is_null() || Object::get_prototype_of(&proto).is_null()
+        }
+        None => false,
+    }
+}
+
+/// Checks whether `value` is an instance of a class registered on the `serializer`,
+/// and `other` is an object with the same prototype.
+pub(crate) fn is_same_class(value: &JsValue, other: &JsValue, serializer: &Serializer) -> bool {
+    serializer.is_class_instance(value)
+        && other.is_object()
+        && Object::is(
+            &Object::get_prototype_of(value),
+            &Object::get_prototype_of(other),
+        )
+}
+
+/// Checks whether `existing` is a JS object that `fresh` can be merged into.
+fn is_mergeable(existing: &JsValue, fresh: &JsValue, serializer: &Serializer) -> bool {
+    if Array::is_array(fresh) {
+        Array::is_array(existing)
+    } else if fresh.has_type::<Map>() {
+        existing.has_type::<Map>()
+    } else if is_plain_object(fresh) {
+        // Existing objects might be class instances or proxies used by UI frameworks
+        // for change tracking, so only exclude the containers handled above.
+        existing.is_object()
+            && !Array::is_array(existing)
+            && !existing.has_type::<Map>()
+            && !existing.has_type::<Set>()
+            && !existing.has_type::<Uint8Array>()
+    } else {
+        // Keep existing instances of registered classes, as long as they're of the same class.
+        is_same_class(fresh, existing, serializer)
+    }
+}
+
+pub(crate) fn same_bytes(existing: &JsValue, fresh: &JsValue) -> bool {
+    match (
+        existing.dyn_ref::<Uint8Array>(),
+        fresh.dyn_ref::<Uint8Array>(),
+    ) {
+        (Some(existing), Some(fresh)) => existing.to_vec() == fresh.to_vec(),
+        _ => false,
+    }
+}
+
+/// Location of a value within the root being updated.
+///
+/// It's only turned into a JSON Pointer when a change is recorded,
+/// so that unchanged values don't cost any allocations.
+enum Path<'a> {
+    Root,
+    Index(&'a Path<'a>, u32),
+    Key(&'a Path<'a>, &'a JsValue),
+}
+
+impl Path<'_> {
+    fn to_pointer(&self) -> String {
+        match self {
+            Self::Root => String::new(),
+            Self::Index(parent, index) => join(&parent.to_pointer(), index),
+            Self::Key(parent, key) => join(&parent.to_pointer(), map_key_segment(key)),
+        }
+    }
+}
+
+/// Updates `existing` in place to match `fresh`, recording the paths of all
+/// written or deleted properties, elements and entries in `changes`.
+///
+/// Returns `false` if `existing` has to be replaced with `fresh` instead.
+fn reconcile(
+    existing: &JsValue,
+    fresh: &JsValue,
+    path: &Path,
+    changes: &mut Vec<String>,
+    serializer: &Serializer,
+) -> Result<bool> {
+    if Object::is(existing, fresh) || same_bytes(existing, fresh) {
+        return Ok(true);
+    }
+    if !is_mergeable(existing, fresh, serializer) {
+        return Ok(false);
+    }
+    if let Some(fresh) = fresh.dyn_ref::<Array>() {
+        let existing = existing.unchecked_ref::<Array>();
+        for (i, item) in fresh.iter().enumerate() {
+            let i = i as u32;
+            let path = Path::Index(path, i);
+            if i >= existing.length()
+                || !reconcile(&existing.get(i), &item, &path, changes, serializer)?
+            {
+                existing.set(i, item);
+                changes.push(path.to_pointer());
+            }
+        }
+        for i in fresh.length()..existing.length() {
+            changes.push(Path::Index(path, i).to_pointer());
+        }
+        if existing.length() > fresh.length() {
+            existing.set_length(fresh.length());
+        }
+    } else if let Some(fresh) = fresh.dyn_ref::<Map>() {
+        let existing = existing.unchecked_ref::<Map>();
+        for entry in fresh.entries().into_iter() {
+            let entry = entry?.unchecked_into::<Array>();
+            let (key, value) = (entry.get(0), entry.get(1));
+            let path = Path::Key(path, &key);
+            if !existing.has(&key)
+                || !reconcile(&existing.get(&key), &value, &path, changes, serializer)?
+            {
+                existing.set(&key, &value);
+                changes.push(path.to_pointer());
+            }
+        }
+        let stale = existing
+            .keys()
+            .into_iter()
+            .collect::<std::result::Result<Vec<_>, JsValue>>()?;
+        for key in stale.into_iter().filter(|key| !fresh.has(key)) {
+            existing.delete(&key);
+            changes.push(Path::Key(path, &key).to_pointer());
+        }
+    } else {
+        let target = existing.unchecked_ref::<ObjectExt>();
+        let fresh_object = fresh.unchecked_ref::<Object>();
+        for entry in Object::entries(fresh_object).iter() {
+            let entry = entry.unchecked_into::<Array>();
+            let (key, value) = (entry.get(0), entry.get(1));
+            let path = Path::Key(path, &key);
+            // Inherited properties such as `toString` have to be shadowed by own ones.
+            if !Object::has_own(existing.unchecked_ref::<Object>(), &key)
+                || !reconcile(
+                    &target.get_with_ref_key(key.unchecked_ref()),
+                    &value,
+                    &path,
+                    changes,
+                    serializer,
+                )?
+            {
+                target.set(key.clone().unchecked_into(), value);
+                changes.push(path.to_pointer());
+            }
+        }
+        for key in Object::keys(existing.unchecked_ref::<Object>()).iter() {
+            if !Object::has_own(fresh_object, &key) {
+                js_sys::Reflect::delete_property(existing.unchecked_ref::<Object>(), &key)?;
+                changes.push(Path::Key(path, &key).to_pointer());
+            }
+        }
+    }
+    Ok(true)
+}
+
+pub(crate) fn map_key_segment(key: &JsValue) -> String {
+    match key.as_string() {
+        Some(key) => key,
+        None => match key.as_f64() {
+            Some(key) => key.to_string(),
+            None => format!("{key:?}"),
+        },
+    }
+}
+
+/// Like [`reconcile`], but fails if the root itself would have to be replaced,
+/// since the caller's reference to it can't be updated.
+pub(crate) fn reconcile_root(
+    existing: &JsValue,
+    fresh: &JsValue,
+    serializer: &Serializer,
+) -> Result<Vec<String>> {
+    let mut changes = Vec::new();
+    if reconcile(existing, fresh, &Path::Root, &mut changes, serializer)? {
+        Ok(changes)
+    } else {
+        Err(Error::new(
+            "existing value can't be updated in place, as it's not an object of a matching type",
+        ))
+    }
+}
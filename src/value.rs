@@ -0,0 +1,424 @@
+use js_sys::{Array, JsString, Map, Object, Set, Symbol, Uint8Array};
+use serde::de::{self, Error as _};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fmt;
+use wasm_bindgen::convert::FromWasmAbi;
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+
+use crate::{Error, ObjectExt, Result};
+
+// Some arbitrary string that no one will collide with unless they try.
+pub(crate) const VALUE_MAGIC: &str = "8b0e4c5d-7a0f-4e4c-9c36-5b1f2a7d9e61";
+
+/// An owned representation of any JavaScript value that [`Serializer`](crate::Serializer) can
+/// produce and [`Deserializer`](crate::Deserializer) can consume.
+///
+/// Unlike `serde_json::Value`, this keeps apart `undefined` and `null`, `BigInt`s and numbers,
+/// bytes, objects and `Map`s (which may have non-string keys), as well as arrays and `Set`s,
+/// so converting a [`JsValue`] into a `Value` and back is lossless. Since it doesn't hold any
+/// references to JavaScript objects, it is `Send` and can be stored or passed between threads.
+///
+/// When used with other Serde formats, `Value` is represented like the corresponding Rust types:
+/// `undefined` and `null` become unit and `None`, objects and `Map`s become maps, and arrays and
+/// `Set`s become sequences. Note that inside internally tagged or untagged enums, which Serde
+/// has to buffer, `Value` goes through the same generic representation even with this crate.
+///
+/// Instances of classes, including those registered with [`Serializer::class`](crate::Serializer::class),
+/// are read as plain objects of their own enumerable properties, so they lose their prototype.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// `undefined`.
+    #[default]
+    Undefined,
+    /// `null`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A number.
+    Number(f64),
+    /// A `BigInt` within the `i128` range.
+    BigInt(i128),
+    /// A `BigInt` above `i128::MAX`.
+    BigUint(u128),
+    /// A string.
+    String(String),
+    /// A `Uint8Array` or an `ArrayBuffer`.
+    Bytes(Vec<u8>),
+    /// An array, or any other iterable that isn't covered by other variants.
+    Array(Vec<Value>),
+    /// A plain object or a class instance, with own properties in their enumeration order.
+    Object(Vec<(String, Value)>),
+    /// An ES2015 `Map`, with entries in their insertion order.
+    Map(Vec<(Value, Value)>),
+    /// An ES2015 `Set`, with values in their insertion order.
+    Set(Vec<Value>),
+}
+
+impl Value {
+    /// Converts a [`JsValue`] into a `Value` tree.
+    ///
+    /// Fails on cyclic values, which can't be represented as a tree.
+    pub(crate) fn from_js(value: &JsValue) -> Result<Self> {
+        Self::from_js_in(value, &Set::new(&JsValue::UNDEFINED))
+    }
+
+    /// Like [`Value::from_js`], but keeps track of the objects being converted
+    /// further up the tree in `ancestors`.
+    fn from_js_in(value: &JsValue, ancestors: &Set) -> Result<Self> {
+        if value.is_undefined() {
+            return Ok(Self::Undefined);
+        }
+        if value.is_null() {
+            return Ok(Self::Null);
+        }
+        if let Some(v) = value.as_bool() {
+            return Ok(Self::Bool(v));
+        }
+        if let Some(v) = value.as_f64() {
+            return Ok(Self::Number(v));
+        }
+        if let Some(v) = value.as_string() {
+            return Ok(Self::String(v));
+        }
+        if value.is_bigint() {
+            return match i128::try_from(value.clone()) {
+                Ok(v) => Ok(Self::BigInt(v)),
+                Err(value) => u128::try_from(value).map(Self::BigUint).map_err(|_| {
+                    Error::new("Couldn't convert a BigInt outside i128::MIN..u128::MAX bounds")
+                }),
+            };
+        }
+        if !value.is_object() {
+            return Err(Error::new(format_args!(
+                "{} can't be converted into a Value",
+                value.js_typeof().as_string().unwrap_throw()
+            )));
+        }
+        // Boxed primitives don't have any own properties, so unwrap them instead.
+        if value.is_instance_of::<js_sys::Number>()
+            || value.is_instance_of::<JsString>()
+            || value.is_instance_of::<js_sys::Boolean>()
+        {
+            return Self::from_js_in(&value.unchecked_ref::<Object>().value_of(), ancestors);
+        }
+        if ancestors.has(value) {
+            return Err(Error::new("Couldn't convert a cyclic value"));
+        }
+        ancestors.add(value);
+        let result = Self::from_js_object(value, ancestors);
+        ancestors.delete(value);
+        result
+    }
+
+    /// Converts an object that isn't already among the `ancestors`.
+    fn from_js_object(value: &JsValue, ancestors: &Set) -> Result<Self> {
+        if let Some(v) = value.dyn_ref::<Uint8Array>() {
+            return Ok(Self::Bytes(v.to_vec()));
+        }
+        if let Some(v) = value.dyn_ref::<js_sys::ArrayBuffer>() {
+            return Ok(Self::Bytes(Uint8Array::new(v).to_vec()));
+        }
+        if let Some(map) = value.dyn_ref::<Map>() {
+            let mut entries = Vec::with_capacity(map.size() as usize);
+            for (i, entry) in map.entries().into_iter().enumerate() {
+                let entry = entry?.unchecked_into::<Array>();
+                let key = Self::from_js_in(&entry.get(0), ancestors).map_err(|err| err.at(i))?;
+                let value = Self::from_js_in(&entry.get(1), ancestors).map_err(|err| err.at(i))?;
+                entries.push((key, value));
+            }
+            return Ok(Self::Map(entries));
+        }
+        if let Some(set) = value.dyn_ref::<Set>() {
+            return Ok(Self::Set(Self::from_js_iter(
+                set.values().into_iter(),
+                ancestors,
+            )?));
+        }
+        if let Some(array) = value.dyn_ref::<Array>() {
+            return Ok(Self::Array(Self::from_js_iter(
+                array.iter().map(Ok),
+                ancestors,
+            )?));
+        }
+        if Symbol::iterator().js_in(value) {
+            let iter = js_sys::try_iter(value)?.unwrap_throw();
+            return Ok(Self::Array(Self::from_js_iter(iter, ancestors)?));
+        }
+        let mut properties = Vec::new();
+        for entry in Object::entries(value.unchecked_ref()).iter() {
+            let entry = entry.unchecked_into::<Array>();
+            let key = entry.get(0).as_string().unwrap_throw();
+            let value = Self::from_js_in(&entry.get(1), ancestors).map_err(|err| err.at(&key))?;
+            properties.push((key, value));
+        }
+        Ok(Self::Object(properties))
+    }
+
+    fn from_js_iter(
+        iter: impl Iterator<Item = std::result::Result<JsValue, JsValue>>,
+        ancestors: &Set,
+    ) -> Result<Vec<Self>> {
+        iter.enumerate()
+            .map(|(i, item)| Self::from_js_in(&item?, ancestors).map_err(|err| err.at(i)))
+            .collect()
+    }
+
+    /// Converts a `Value` tree back into a [`JsValue`].
+    pub(crate) fn to_js(&self) -> JsValue {
+        match self {
+            Self::Undefined => JsValue::UNDEFINED,
+            Self::Null => JsValue::NULL,
+            Self::Bool(v) => JsValue::from_bool(*v),
+            Self::Number(v) => JsValue::from_f64(*v),
+            Self::BigInt(v) => JsValue::from(*v),
+            Self::BigUint(v) => JsValue::from(*v),
+            Self::String(v) => JsValue::from_str(v),
+            Self::Bytes(v) => Uint8Array::from(v.as_slice()).into(),
+            Self::Array(items) => items.iter().map(Self::to_js).collect::<Array>().into(),
+            Self::Object(properties) => {
+                let object = Object::new();
+                for (key, value) in properties {
+                    object
+                        .unchecked_ref::<ObjectExt>()
+                        .set(key.as_str().into(), value.to_js());
+                }
+                object.into()
+            }
+            Self::Map(entries) => {
+                let map = Map::new();
+                for (key, value) in entries {
+                    map.set(&key.to_js(), &value.to_js());
+                }
+                map.into()
+            }
+            Self::Set(items) => {
+                let set = Set::new(&JsValue::UNDEFINED);
+                for item in items {
+                    set.add(&item.to_js());
+                }
+                set.into()
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// The [`Value`] that is currently being passed to a serializer by [`Value::serialize`].
+    static SERIALIZING: Cell<*const Value> = const { Cell::new(std::ptr::null()) };
+}
+
+/// Returns the [`Value`] behind the payload of a [`VALUE_MAGIC`] newtype struct,
+/// as long as it's the one that [`Value::serialize`] is currently passing to the serializer.
+pub(crate) fn serializing_value<T: ?Sized>(payload: &T) -> Option<&Value> {
+    let ptr = (payload as *const T).cast::<Value>();
+    if SERIALIZING.with(Cell::get) == ptr {
+        // The pointer was registered from a live reference by `Value::serialize`,
+        // which is still on the stack, and the payload is the same `GenericValue`,
+        // which is a transparent wrapper around that `Value`.
+        Some(unsafe { &*ptr })
+    } else {
+        None
+    }
+}
+
+/// Mirrors `Number.isSafeInteger`, except that `-0` is kept as a float.
+fn is_safe_integer(v: f64) -> bool {
+    v.fract() == 0.0
+        && v.abs() <= js_sys::Number::MAX_SAFE_INTEGER
+        && !(v == 0.0 && v.is_sign_negative())
+}
+
+/// A transparent wrapper that serializes a [`Value`] via the generic Serde data model.
+#[repr(transparent)]
+struct GenericValue(Value);
+
+impl GenericValue {
+    const fn from_ref(value: &Value) -> &Self {
+        // Safe thanks to `#[repr(transparent)]`.
+        unsafe { &*(value as *const Value).cast::<Self>() }
+    }
+}
+
+impl ser::Serialize for GenericValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match &self.0 {
+            Value::Undefined => serializer.serialize_unit(),
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            // Like `Deserializer::deserialize_any`, pass safe integers as such,
+            // so that e.g. JSON doesn't end up with `1.0`.
+            Value::Number(v) if is_safe_integer(*v) => serializer.serialize_i64(*v as i64),
+            Value::Number(v) => serializer.serialize_f64(*v),
+            Value::BigInt(v) => serializer.serialize_i128(*v),
+            Value::BigUint(v) => serializer.serialize_u128(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::Array(items) | Value::Set(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Object(properties) => {
+                let mut map = serializer.serialize_map(Some(properties.len()))?;
+                for (key, value) in properties {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Value::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl ser::Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // `serde_wasm_bindgen::Serializer` recognises the magic name and converts
+        // the registered `Value` directly, all others see just the generic representation.
+        let prev = SERIALIZING.with(|cell| cell.replace(self));
+        let result = serializer.serialize_newtype_struct(VALUE_MAGIC, GenericValue::from_ref(self));
+        SERIALIZING.with(|cell| cell.set(prev));
+        result
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Value, E> {
+        self.visit_i128(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> std::result::Result<Value, E> {
+        const MAX_SAFE_INTEGER: i128 = js_sys::Number::MAX_SAFE_INTEGER as i128;
+
+        Ok(if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+            Value::Number(v as f64)
+        } else {
+            Value::BigInt(v)
+        })
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Value, E> {
+        self.visit_u128(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> std::result::Result<Value, E> {
+        match i128::try_from(v) {
+            Ok(v) => self.visit_i128(v),
+            Err(_) => Ok(Value::BigUint(v)),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::Number(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(Value::Bytes(v.to_owned()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> std::result::Result<Value, D::Error> {
+        d.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        d: D,
+    ) -> std::result::Result<Value, D::Error> {
+        d.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// Collects maps with only string keys into [`Value::Object`], and all others into [`Value::Map`].
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        if entries
+            .iter()
+            .all(|(key, _)| matches!(key, Value::String(_)))
+        {
+            Ok(Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| match key {
+                        Value::String(key) => (key, value),
+                        _ => unreachable!(),
+                    })
+                    .collect(),
+            ))
+        } else {
+            Ok(Value::Map(entries))
+        }
+    }
+
+    /// Only `serde_wasm_bindgen::Deserializer` visits [`VALUE_MAGIC`] as an enum,
+    /// passing the original JS value through as a newtype variant.
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> std::result::Result<Value, A::Error> {
+        let (variant, payload) = data.variant::<String>()?;
+        if variant != VALUE_MAGIC {
+            return Err(A::Error::invalid_type(de::Unexpected::Enum, &self));
+        }
+        let abi = de::VariantAccess::newtype_variant::<u32>(payload)?;
+        // Same reasoning as in `serde_wasm_bindgen::preserve::deserialize`: our deserializer
+        // just converted a `JsValue` into ABI, and others are very unlikely to end up here.
+        let value = unsafe { JsValue::from_abi(abi) };
+        Value::from_js(&value).map_err(A::Error::custom)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_MAGIC, ValueVisitor)
+    }
+}
@@ -911,3 +911,2136 @@ fn field_aliases() {
 
     test_via_round_trip_with_config(Struct { a: 42, c: 84 }, &SERIALIZER);
 }
+
+#[wasm_bindgen_test]
+fn structs_from_maps() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Struct {
+        a: i32,
+        #[serde(default)]
+        b: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct StrictStruct {
+        a: i32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Enum {
+        Struct { a: i32 },
+    }
+
+    let map = js_sys::Map::new();
+    map.set(&"a".into(), &42.into());
+    map.set(&"c".into(), &"unknown".into());
+
+    assert_eq!(
+        from_value::<Struct>(map.clone().into()).unwrap(),
+        Struct { a: 42, b: None }
+    );
+
+    // Unknown keys in a `Map` are reported to the struct visitor.
+    from_value::<StrictStruct>(map.clone().into()).unwrap_err();
+
+    map.delete(&"c".into());
+    assert_eq!(
+        from_value::<StrictStruct>(map.clone().into()).unwrap(),
+        StrictStruct { a: 42 }
+    );
+
+    // Keys other than strings can't be fields, so they're skipped rather than
+    // being rejected or treated as field indices.
+    map.set(&0.into(), &"zero".into());
+    assert_eq!(
+        from_value::<Struct>(map.clone().into()).unwrap(),
+        Struct { a: 42, b: None }
+    );
+    assert_eq!(
+        from_value::<StrictStruct>(map.clone().into()).unwrap(),
+        StrictStruct { a: 42 }
+    );
+    map.delete(&0.into());
+
+    let outer = js_sys::Map::new();
+    outer.set(&"Struct".into(), &map.clone().into());
+    assert_eq!(
+        from_value::<Enum>(outer.into()).unwrap(),
+        Enum::Struct { a: 42 }
+    );
+
+    // Missing fields are still detected.
+    map.delete(&"a".into());
+    from_value::<Struct>(map.into()).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn maps_objects_stringified_keys() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Key {
+        A,
+        B,
+    }
+
+    test_via_json_with_config(
+        btreemap! {
+            1_u32 => "one".to_string(),
+            42_u32 => "forty-two".to_string(),
+        },
+        &MAP_OBJECT_SERIALIZER,
+    );
+    test_via_json_with_config(
+        btreemap! {
+            u64::MAX => 1,
+        },
+        &MAP_OBJECT_SERIALIZER,
+    );
+    test_via_json_with_config(btreemap! { true => 1 }, &MAP_OBJECT_SERIALIZER);
+    test_via_json_with_config(btreemap! { 'x' => 1 }, &MAP_OBJECT_SERIALIZER);
+    test_via_json_with_config(
+        btreemap! {
+            Key::A => 1,
+            Key::B => 2,
+        },
+        &JSON_SERIALIZER,
+    );
+
+    // Keys that don't parse into the requested type are still rejected.
+    let object = js_sys::JSON::parse(r#"{"x": 1}"#).unwrap();
+    from_value::<BTreeMap<u32, i32>>(object).unwrap_err();
+
+    // Floats are not valid keys.
+    let res = serde::Serializer::collect_map(&MAP_OBJECT_SERIALIZER, vec![(1.5_f64, 1)]);
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        Error::custom("Map key is not a string and cannot be an object key").to_string()
+    );
+}
+
+#[wasm_bindgen_test]
+fn maps_string_keyed_as_objects() {
+    use serde::Serializer as _;
+
+    let serializer = Serializer::new().serialize_string_keyed_maps_as_objects(true);
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Struct {
+        strings: HashMap<String, i32>,
+        tuples: BTreeMap<(u32, u32), i32>,
+    }
+
+    let value = Struct {
+        strings: hashmap! { "a".to_string() => 1 },
+        tuples: btreemap! { (1, 2) => 3 },
+    };
+    let res = test_via_round_trip_with_config(value, &serializer);
+    let strings = js_sys::Reflect::get(&res, &"strings".into()).unwrap();
+    assert!(!strings.has_type::<js_sys::Map>());
+    assert_eq!(js_sys::JSON::stringify(&strings).unwrap(), r#"{"a":1}"#);
+    let tuples = js_sys::Reflect::get(&res, &"tuples".into()).unwrap();
+    assert!(tuples.has_type::<js_sys::Map>());
+
+    // Order of string keys is preserved.
+    let res = serializer
+        .collect_map(vec![("b", 1), ("a", 2), ("1", 3)])
+        .unwrap();
+    let res = res.dyn_into::<js_sys::Map>().unwrap();
+    assert_eq!(
+        res.keys()
+            .into_iter()
+            .map(|key| key.unwrap().as_string().unwrap())
+            .collect::<Vec<_>>(),
+        ["b", "a", "1"]
+    );
+
+    let res = serializer.collect_map(vec![("1", 1), ("2", 2)]).unwrap();
+    assert!(!res.has_type::<js_sys::Map>());
+    assert_eq!(js_sys::JSON::stringify(&res).unwrap(), r#"{"1":1,"2":2}"#);
+
+    let res = serializer.collect_map(vec![("2", 1), ("1", 2)]).unwrap();
+    assert!(res.has_type::<js_sys::Map>());
+
+    // `__proto__` can't be represented as an own property via assignment.
+    let res = serializer.collect_map(vec![("__proto__", 1)]).unwrap();
+    assert!(res.has_type::<js_sys::Map>());
+}
+
+#[wasm_bindgen_test]
+fn flattened_structs_as_objects() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Inner {
+        b: i32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Outer {
+        a: i32,
+        #[serde(flatten)]
+        inner: Inner,
+        #[serde(flatten)]
+        rest: BTreeMap<String, i32>,
+    }
+
+    let value = Outer {
+        a: 1,
+        inner: Inner { b: 2 },
+        rest: btreemap! { "c".to_string() => 3 },
+    };
+
+    // Same shape as without `#[serde(flatten)]` regardless of the map configuration.
+    let res = test_via_round_trip_with_config(value, &SERIALIZER);
+    assert!(!res.has_type::<js_sys::Map>());
+    assert_eq!(
+        js_sys::JSON::stringify(&res).unwrap(),
+        r#"{"a":1,"b":2,"c":3}"#
+    );
+
+    // Real maps still follow `serialize_maps_as_objects`.
+    let res = to_value(&btreemap! { "a".to_string() => 1 }).unwrap();
+    assert!(res.has_type::<js_sys::Map>());
+
+    // So do real maps of unknown length, keeping integer-like keys in their original order.
+    struct Filtered(Vec<(&'static str, i32)>);
+
+    impl Serialize for Filtered {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().filter(|(_, v)| *v > 0).map(|(k, v)| (k, v)))
+        }
+    }
+
+    let res = to_value(&Filtered(vec![("b", 1), ("1", 2), ("c", 0)])).unwrap();
+    let map = res.dyn_into::<js_sys::Map>().unwrap();
+    assert_eq!(
+        js_sys::Array::from(&map.keys())
+            .join(",")
+            .as_string()
+            .unwrap(),
+        "b,1"
+    );
+
+    // Non-string keys coming from a flattened map fall back to a `Map`.
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct WithNumericKeys {
+        a: i32,
+        #[serde(flatten)]
+        rest: BTreeMap<(u32, u32), i32>,
+    }
+
+    let res = to_value(&WithNumericKeys {
+        a: 1,
+        rest: btreemap! { (2, 3) => 4 },
+    })
+    .unwrap();
+    assert!(res.has_type::<js_sys::Map>());
+}
+
+#[wasm_bindgen_test]
+fn rest_properties() {
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Struct {
+        a: i32,
+        #[serde(flatten, with = "serde_wasm_bindgen::rest")]
+        rest: Object,
+    }
+
+    let nested = Object::new();
+    let date = js_sys::Date::new_0();
+    let obj = Object::new();
+    js_sys::Reflect::set(&obj, &"a".into(), &1.into()).unwrap();
+    js_sys::Reflect::set(&obj, &"nested".into(), &nested).unwrap();
+    js_sys::Reflect::set(&obj, &"date".into(), &date).unwrap();
+
+    let res: Struct = from_value(obj.into()).unwrap();
+    assert_eq!(res.a, 1);
+
+    // Unknown properties keep their identity.
+    assert_eq!(
+        js_sys::Reflect::get(&res.rest, &"nested".into()).unwrap(),
+        JsValue::from(&nested)
+    );
+    assert_eq!(
+        js_sys::Reflect::get(&res.rest, &"date".into()).unwrap(),
+        JsValue::from(&date)
+    );
+    assert!(!js_sys::Reflect::has(&res.rest, &"a".into()).unwrap());
+
+    // And are merged back on serialization.
+    let res = to_value(&res).unwrap();
+    assert!(!res.has_type::<js_sys::Map>());
+    assert_eq!(js_sys::Reflect::get(&res, &"a".into()).unwrap(), 1);
+    assert_eq!(
+        js_sys::Reflect::get(&res, &"nested".into()).unwrap(),
+        JsValue::from(&nested)
+    );
+    assert_eq!(
+        js_sys::Reflect::get(&res, &"date".into()).unwrap(),
+        JsValue::from(&date)
+    );
+
+    // Other deserializers can't provide the original JS values.
+    serde_json::from_str::<Struct>(r#"{"a": 1, "b": 2}"#).unwrap_err();
+
+    // Values that don't fit into the Serde data model are captured too.
+    let obj = Object::new();
+    let huge = BigInt::new(&"1000000000000000000000000000000000000000000".into()).unwrap();
+    let values: [(&str, JsValue); 5] = [
+        ("set", js_sys::Set::new(&JsValue::UNDEFINED).into()),
+        ("function", js_sys::Function::new_no_args("").into()),
+        ("int8", js_sys::Int8Array::new_with_length(2).into()),
+        ("huge", huge.into()),
+        (
+            "nested",
+            js_sys::Array::of1(&js_sys::Set::new(&JsValue::UNDEFINED)).into(),
+        ),
+    ];
+    js_sys::Reflect::set(&obj, &"a".into(), &1.into()).unwrap();
+    for (key, value) in &values {
+        js_sys::Reflect::set(&obj, &(*key).into(), value).unwrap();
+    }
+    let res: Struct = from_value(obj.into()).unwrap();
+    for (key, value) in &values {
+        assert_eq!(
+            js_sys::Reflect::get(&res.rest, &(*key).into()).unwrap(),
+            *value
+        );
+    }
+
+    // Structs buffered by an outer flattened struct can't see their own JS object,
+    // which is reported instead of reading properties of the outer one.
+    #[derive(Debug, Deserialize)]
+    struct Middle {
+        #[allow(dead_code)]
+        inner: Struct,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Outer {
+        #[allow(dead_code)]
+        #[serde(flatten)]
+        middle: Middle,
+    }
+    let js = js_sys::JSON::parse(r#"{"inner": {"a": 1, "b": 2}}"#).unwrap();
+    let err = from_value::<Outer>(js).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("property `b` is not found on the object being deserialized"));
+
+    // Even if the outer object happens to have a property with the same name.
+    let js = js_sys::JSON::parse(r#"{"inner": {"a": 1, "b": 2}, "b": 3}"#).unwrap();
+    let err = from_value::<Outer>(js).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("property `b` is not found on the object being deserialized"));
+}
+
+#[wasm_bindgen_test]
+fn enum_representation_override() {
+    use serde_wasm_bindgen::{Deserializer, EnumRepresentation};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Payload {
+        x: i32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Plain {
+        Unit,
+        Newtype(Payload),
+        Tuple(i32, i32),
+        Struct { y: i32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum Internal {
+        Unit,
+        Newtype(Payload),
+        Struct { y: i32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        Unit,
+        Newtype(Payload),
+        Tuple(i32, i32),
+        Struct { y: i32 },
+    }
+
+    fn check<R>(value: Plain, representation: EnumRepresentation, expected: R)
+    where
+        R: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let serializer = Serializer::new().enum_representation(representation);
+        let js = value.serialize(&serializer).unwrap();
+        assert_json(js.clone(), expected);
+        let restored =
+            Plain::deserialize(Deserializer::from(js).enum_representation(representation)).unwrap();
+        assert_eq!(restored, value);
+    }
+
+    const INTERNAL: EnumRepresentation = EnumRepresentation::InternallyTagged { tag: "type" };
+    const ADJACENT: EnumRepresentation = EnumRepresentation::AdjacentlyTagged {
+        tag: "t",
+        content: "c",
+    };
+
+    check(Plain::Unit, INTERNAL, Internal::Unit);
+    check(
+        Plain::Newtype(Payload { x: 1 }),
+        INTERNAL,
+        Internal::Newtype(Payload { x: 1 }),
+    );
+    check(Plain::Struct { y: 2 }, INTERNAL, Internal::Struct { y: 2 });
+
+    check(Plain::Unit, ADJACENT, Adjacent::Unit);
+    check(
+        Plain::Newtype(Payload { x: 1 }),
+        ADJACENT,
+        Adjacent::Newtype(Payload { x: 1 }),
+    );
+    check(Plain::Tuple(1, 2), ADJACENT, Adjacent::Tuple(1, 2));
+    check(Plain::Struct { y: 2 }, ADJACENT, Adjacent::Struct { y: 2 });
+
+    // Tuple variants can't be internally tagged.
+    Plain::Tuple(1, 2)
+        .serialize(&Serializer::new().enum_representation(INTERNAL))
+        .unwrap_err();
+
+    // Nullish newtype payloads can't be internally tagged either, as they wouldn't round-trip.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Optional {
+        Unit(()),
+        Maybe(Option<Payload>),
+    }
+    let internal = Serializer::new().enum_representation(INTERNAL);
+    Optional::Unit(()).serialize(&internal).unwrap_err();
+    Optional::Maybe(None).serialize(&internal).unwrap_err();
+    let value = Optional::Maybe(Some(Payload { x: 1 }));
+    let js = value.serialize(&internal).unwrap();
+    assert_json(js.clone(), serde_json::json!({ "type": "Maybe", "x": 1 }));
+    let restored =
+        Optional::deserialize(Deserializer::from(js).enum_representation(INTERNAL)).unwrap();
+    assert_eq!(restored, value);
+
+    // Plain strings are still accepted for unit variants.
+    let restored =
+        Plain::deserialize(Deserializer::from(JsValue::from("Unit")).enum_representation(INTERNAL))
+            .unwrap();
+    assert_eq!(restored, Plain::Unit);
+
+    // Payload fields named like the tag would overwrite it.
+    #[derive(Serialize)]
+    struct Typed {
+        r#type: i32,
+    }
+
+    #[derive(Serialize)]
+    enum Colliding {
+        Newtype(Typed),
+        Struct { r#type: i32 },
+    }
+
+    Colliding::Newtype(Typed { r#type: 1 })
+        .serialize(&internal)
+        .unwrap_err();
+    Colliding::Struct { r#type: 1 }
+        .serialize(&internal)
+        .unwrap_err();
+
+    // Tags and content can be read from `Map`s too.
+    let map = |entries: &[(&str, JsValue)]| {
+        let map = js_sys::Map::new();
+        for (key, value) in entries {
+            map.set(&JsValue::from(*key), value);
+        }
+        JsValue::from(map)
+    };
+    let from_map = |js: JsValue, representation| {
+        Plain::deserialize(Deserializer::from(js).enum_representation(representation)).unwrap()
+    };
+    assert_eq!(
+        from_map(map(&[("type", "Struct".into()), ("y", 2.into())]), INTERNAL),
+        Plain::Struct { y: 2 }
+    );
+    assert_eq!(
+        from_map(
+            map(&[("type", "Newtype".into()), ("x", 1.into())]),
+            INTERNAL
+        ),
+        Plain::Newtype(Payload { x: 1 })
+    );
+    assert_eq!(
+        from_map(
+            map(&[("t", "Newtype".into()), ("c", map(&[("x", 1.into())]))]),
+            ADJACENT
+        ),
+        Plain::Newtype(Payload { x: 1 })
+    );
+}
+
+#[wasm_bindgen_test]
+fn unit_variants_as_numbers() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Enum {
+        A,
+        B,
+        C(i32),
+    }
+
+    let serializer = Serializer::new().serialize_unit_variants_as_numbers(true);
+
+    let value = test_via_round_trip_with_config(Enum::B, &serializer);
+    assert_eq!(value, 1);
+
+    // Other variants are unaffected.
+    test_via_round_trip_with_config(Enum::C(42), &serializer);
+
+    // Names are still accepted.
+    assert_eq!(from_value::<Enum>("A".into()).unwrap(), Enum::A);
+
+    // Indices are validated.
+    from_value::<Enum>(3.into()).unwrap_err();
+    from_value::<Enum>(0.5.into()).unwrap_err();
+    from_value::<Enum>((-1).into()).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn rename_all() {
+    use serde_wasm_bindgen::{Deserializer, RenameRule};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Struct {
+        field_name: i32,
+        nested_enum: Enum,
+        other_enums: Vec<Enum>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    enum Enum {
+        UnitVariant,
+        StructVariant { inner_field: bool },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct CamelStruct {
+        field_name: i32,
+        nested_enum: CamelEnum,
+        other_enums: Vec<CamelEnum>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    enum CamelEnum {
+        UnitVariant,
+        #[serde(rename_all = "camelCase")]
+        StructVariant {
+            inner_field: bool,
+        },
+    }
+
+    let value = Struct {
+        field_name: 1,
+        nested_enum: Enum::UnitVariant,
+        other_enums: vec![Enum::StructVariant { inner_field: true }],
+    };
+
+    let serializer = Serializer::new().rename_all(RenameRule::CamelCase);
+    let js = value.serialize(&serializer).unwrap();
+    assert_json(
+        js.clone(),
+        CamelStruct {
+            field_name: 1,
+            nested_enum: CamelEnum::UnitVariant,
+            other_enums: vec![CamelEnum::StructVariant { inner_field: true }],
+        },
+    );
+    let restored =
+        Struct::deserialize(Deserializer::from(js).rename_all(RenameRule::CamelCase)).unwrap();
+    assert_eq!(restored, value);
+
+    for (rule, field, variant) in [
+        (RenameRule::PascalCase, "FieldName", "UnitVariant"),
+        (RenameRule::KebabCase, "field-name", "unit-variant"),
+        (RenameRule::ScreamingSnakeCase, "FIELD_NAME", "UNIT_VARIANT"),
+    ] {
+        let js = value
+            .serialize(&Serializer::new().rename_all(rule))
+            .unwrap();
+        assert!(js_sys::Reflect::has(&js, &field.into()).unwrap());
+        let js_variant = value
+            .nested_enum
+            .serialize(&Serializer::new().rename_all(rule))
+            .unwrap();
+        assert_eq!(js_variant, variant);
+        let restored = Struct::deserialize(Deserializer::from(js).rename_all(rule)).unwrap();
+        assert_eq!(restored, value);
+    }
+
+    // Structs with flattened fields are maps for Serde, so none of their own fields are renamed.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Flattened {
+        own_field: i32,
+        #[serde(flatten)]
+        inner: Struct,
+    }
+    let value = Flattened {
+        own_field: 2,
+        inner: value,
+    };
+    let js = value.serialize(&serializer).unwrap();
+    assert!(js_sys::Reflect::has(&js, &"own_field".into()).unwrap());
+    assert!(js_sys::Reflect::has(&js, &"field_name".into()).unwrap());
+    assert!(!js_sys::Reflect::has(&js, &"ownField".into()).unwrap());
+    // But Serde buffers their values and can't apply the rule to the nested ones,
+    // which is reported instead of ignoring their renamed fields.
+    let err = Flattened::deserialize(Deserializer::from(js).rename_all(RenameRule::CamelCase))
+        .unwrap_err();
+    assert!(err.to_string().contains("#[serde(flatten)]"));
+
+    // Unit variants in map keys are renamed too.
+    let js = btreemap! { Enum::UnitVariant => 1 }
+        .serialize(
+            &Serializer::new()
+                .rename_all(RenameRule::CamelCase)
+                .serialize_maps_as_objects(true),
+        )
+        .unwrap();
+    assert_eq!(
+        js_sys::JSON::stringify(&js).unwrap(),
+        r#"{"unitVariant":1}"#
+    );
+    let restored = BTreeMap::<Enum, i32>::deserialize(
+        Deserializer::from(js).rename_all(RenameRule::CamelCase),
+    )
+    .unwrap();
+    assert_eq!(restored, btreemap! { Enum::UnitVariant => 1 });
+
+    // Explicitly renamed names are kept.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Explicit {
+        #[serde(rename = "ID")]
+        id: i32,
+        #[serde(rename = "with-dash")]
+        with_dash: i32,
+    }
+    let value = Explicit {
+        id: 1,
+        with_dash: 2,
+    };
+    let js = value.serialize(&serializer).unwrap();
+    assert_eq!(
+        js_sys::JSON::stringify(&js).unwrap(),
+        r#"{"ID":1,"with-dash":2}"#
+    );
+    let restored =
+        Explicit::deserialize(Deserializer::from(js).rename_all(RenameRule::CamelCase)).unwrap();
+    assert_eq!(restored, value);
+}
+
+#[wasm_bindgen_test]
+fn case_insensitive_identifiers() {
+    use serde_wasm_bindgen::Deserializer;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Struct {
+        id: u32,
+        status: Status,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Status {
+        Active,
+        Inactive,
+    }
+
+    fn lenient<T: DeserializeOwned>(value: JsValue) -> Result<T, Error> {
+        T::deserialize(Deserializer::from(value).case_insensitive_identifiers(true))
+    }
+
+    let obj = Object::new();
+    js_sys::Reflect::set(&obj, &"ID".into(), &1.into()).unwrap();
+    js_sys::Reflect::set(&obj, &"Status".into(), &"ACTIVE".into()).unwrap();
+
+    from_value::<Struct>(obj.clone().into()).unwrap_err();
+    assert_eq!(
+        lenient::<Struct>(obj.clone().into()).unwrap(),
+        Struct {
+            id: 1,
+            status: Status::Active,
+        }
+    );
+
+    // Exact matches take precedence.
+    js_sys::Reflect::set(&obj, &"id".into(), &2.into()).unwrap();
+    assert_eq!(lenient::<Struct>(obj.clone().into()).unwrap().id, 2);
+
+    // Ambiguous matches are errors.
+    let obj = Object::new();
+    js_sys::Reflect::set(&obj, &"ID".into(), &1.into()).unwrap();
+    js_sys::Reflect::set(&obj, &"Id".into(), &2.into()).unwrap();
+    js_sys::Reflect::set(&obj, &"status".into(), &"inactive".into()).unwrap();
+    lenient::<Struct>(obj.into()).unwrap_err();
+
+    // Maps work too.
+    let map = js_sys::Map::new();
+    map.set(&"Id".into(), &3.into());
+    map.set(&"STATUS".into(), &"inactive".into());
+    assert_eq!(
+        lenient::<Struct>(map.into()).unwrap(),
+        Struct {
+            id: 3,
+            status: Status::Inactive,
+        }
+    );
+}
+
+#[wasm_bindgen_test]
+fn coercions() {
+    use serde_wasm_bindgen::Deserializer;
+
+    fn coerce<T: DeserializeOwned>(
+        value: impl Into<JsValue>,
+        configure: fn(Deserializer) -> Deserializer,
+    ) -> Result<T, Error> {
+        T::deserialize(configure(Deserializer::from(value.into())))
+    }
+
+    // Numeric strings.
+    from_value::<i32>("42".into()).unwrap_err();
+    let numeric_strings = |de: Deserializer| de.coerce_numeric_strings(true);
+    assert_eq!(coerce::<i32>("-42", numeric_strings).unwrap(), -42);
+    assert_eq!(coerce::<u8>("255", numeric_strings).unwrap(), 255);
+    coerce::<u8>("256", numeric_strings).unwrap_err();
+    coerce::<u32>("-1", numeric_strings).unwrap_err();
+    assert_eq!(
+        coerce::<u64>("18446744073709551615", numeric_strings).unwrap(),
+        u64::MAX
+    );
+    assert_eq!(
+        coerce::<i128>("-170141183460469231731687303715884105728", numeric_strings).unwrap(),
+        i128::MIN
+    );
+    assert_eq!(coerce::<f64>("1.5", numeric_strings).unwrap(), 1.5);
+    coerce::<i32>("1.5", numeric_strings).unwrap_err();
+    coerce::<i32>("abc", numeric_strings).unwrap_err();
+
+    // Boolean strings.
+    from_value::<bool>("true".into()).unwrap_err();
+    let bool_strings = |de: Deserializer| de.coerce_bool_strings(true);
+    assert!(coerce::<bool>("true", bool_strings).unwrap());
+    assert!(!coerce::<bool>("false", bool_strings).unwrap());
+    coerce::<bool>("yes", bool_strings).unwrap_err();
+
+    // Numbers to 128-bit integers.
+    from_value::<i128>(42.into()).unwrap_err();
+    let numbers_to_128 = |de: Deserializer| de.coerce_numbers_to_128_bit_integers(true);
+    assert_eq!(coerce::<i128>(-42, numbers_to_128).unwrap(), -42);
+    assert_eq!(coerce::<u128>(42, numbers_to_128).unwrap(), 42);
+    coerce::<u128>(-42, numbers_to_128).unwrap_err();
+    coerce::<i128>(1.5, numbers_to_128).unwrap_err();
+
+    // BigInts to floats.
+    from_value::<f64>(BigInt::from(42).into()).unwrap_err();
+    let bigints_to_floats = |de: Deserializer| de.coerce_bigints_to_floats(true);
+    assert_eq!(
+        coerce::<f64>(BigInt::from(42), bigints_to_floats).unwrap(),
+        42.0
+    );
+    assert_eq!(
+        coerce::<f64>(BigInt::from(1u64 << 60), bigints_to_floats).unwrap(),
+        (1u64 << 60) as f64
+    );
+    coerce::<f64>(BigInt::from((1u64 << 60) + 1), bigints_to_floats).unwrap_err();
+
+    // Boxed primitives.
+    let unwrap_boxed = |de: Deserializer| de.unwrap_boxed_primitives(true);
+    // Other objects are left as is.
+    coerce::<f64>(Object::new(), unwrap_boxed).unwrap_err();
+    let boxed_number = js_sys::eval("new Number(1.5)").unwrap();
+    let boxed_string = js_sys::eval("new String('abc')").unwrap();
+    let boxed_bool = js_sys::eval("new Boolean(true)").unwrap();
+    from_value::<f64>(boxed_number.clone()).unwrap_err();
+    assert_eq!(coerce::<f64>(boxed_number, unwrap_boxed).unwrap(), 1.5);
+    assert_eq!(coerce::<String>(boxed_string, unwrap_boxed).unwrap(), "abc");
+    assert!(coerce::<bool>(boxed_bool, unwrap_boxed).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn large_numbers_as_strings() {
+    use serde_wasm_bindgen::Deserializer;
+
+    const STRING_SERIALIZER: Serializer =
+        Serializer::new().serialize_large_number_types_as_strings(true);
+    const UNSAFE_STRING_SERIALIZER: Serializer =
+        Serializer::new().serialize_unsafe_integers_as_strings(true);
+
+    fn test_via_strings<T>(value: T, expected: impl Into<JsValue>, serializer: &Serializer)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let serialized = value.serialize(serializer).unwrap();
+        assert_eq!(serialized, expected.into());
+        let round_trip: T =
+            T::deserialize(Deserializer::from(serialized.clone()).coerce_numeric_strings(true))
+                .unwrap();
+        assert_eq!(value, round_trip, "{value:?} == from_value({serialized:?})");
+    }
+
+    // Always as strings.
+    test_via_strings(1_i64, "1", &STRING_SERIALIZER);
+    test_via_strings(i64::MIN, i64::MIN.to_string(), &STRING_SERIALIZER);
+    test_via_strings(u64::MAX, u64::MAX.to_string(), &STRING_SERIALIZER);
+    test_via_strings(i128::MIN, i128::MIN.to_string(), &STRING_SERIALIZER);
+    test_via_strings(u128::MAX, u128::MAX.to_string(), &STRING_SERIALIZER);
+    // Smaller types are unaffected.
+    test_via_strings(1_i32, 1, &STRING_SERIALIZER);
+
+    // Only outside of the safe integer range for 64-bit types, and always for 128-bit ones.
+    let max_safe = Number::MAX_SAFE_INTEGER as i64;
+    test_via_strings(max_safe, max_safe as f64, &UNSAFE_STRING_SERIALIZER);
+    test_via_strings(-max_safe, -max_safe as f64, &UNSAFE_STRING_SERIALIZER);
+    test_via_strings(
+        max_safe + 1,
+        (max_safe + 1).to_string(),
+        &UNSAFE_STRING_SERIALIZER,
+    );
+    test_via_strings(
+        -max_safe - 1,
+        (-max_safe - 1).to_string(),
+        &UNSAFE_STRING_SERIALIZER,
+    );
+    test_via_strings(u64::MAX, u64::MAX.to_string(), &UNSAFE_STRING_SERIALIZER);
+    test_via_strings(42_u128, "42", &UNSAFE_STRING_SERIALIZER);
+    test_via_strings(i128::MAX, i128::MAX.to_string(), &UNSAFE_STRING_SERIALIZER);
+
+    // Strings are only accepted back with `coerce_numeric_strings`.
+    from_value::<u64>(u64::MAX.to_string().into()).unwrap_err();
+    from_value::<i128>("42".into()).unwrap_err();
+
+    // JSON-compatible serializer produces strings instead of `BigInt`s for 128-bit types,
+    // but still rejects unsafe 64-bit integers unless asked to stringify them.
+    test_via_strings(1_i128, "1", &JSON_SERIALIZER);
+    test_via_strings(1_u64, 1, &JSON_SERIALIZER);
+    u64::MAX.serialize(&JSON_SERIALIZER).unwrap_err();
+    test_via_strings(
+        u64::MAX,
+        u64::MAX.to_string(),
+        &Serializer::json_compatible().serialize_unsafe_integers_as_strings(true),
+    );
+
+    // String options take precedence over `BigInt` ones regardless of the order.
+    let bigint_strings = Serializer::new()
+        .serialize_unsafe_integers_as_strings(true)
+        .serialize_large_number_types_as_bigints(true);
+    test_via_strings(u64::MAX, u64::MAX.to_string(), &bigint_strings);
+    let bigint_strings = Serializer::new()
+        .serialize_large_number_types_as_bigints(true)
+        .serialize_unsafe_integers_as_strings(true);
+    test_via_strings(u64::MAX, u64::MAX.to_string(), &bigint_strings);
+
+    // Strings that don't fit the type are rejected.
+    let numeric_strings =
+        |value: &str| Deserializer::from(JsValue::from(value)).coerce_numeric_strings(true);
+    u64::deserialize(numeric_strings("-1")).unwrap_err();
+    i64::deserialize(numeric_strings(&u64::MAX.to_string())).unwrap_err();
+    i64::deserialize(numeric_strings("1.5")).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn unsafe_integers_as_bigints() {
+    const ADAPTIVE_SERIALIZER: Serializer =
+        Serializer::new().serialize_unsafe_integers_as_bigints(true);
+
+    let max_safe = Number::MAX_SAFE_INTEGER as i64;
+    let min_safe = Number::MIN_SAFE_INTEGER as i64;
+
+    // Numbers within the safe integer range stay numbers...
+    test_via_into_with_config(3_usize, 3, &ADAPTIVE_SERIALIZER);
+    test_via_into_with_config(max_safe, max_safe as f64, &ADAPTIVE_SERIALIZER);
+    test_via_into_with_config(min_safe, min_safe as f64, &ADAPTIVE_SERIALIZER);
+    test_via_into_with_config(max_safe as u64, max_safe as f64, &ADAPTIVE_SERIALIZER);
+
+    // ...and others become `BigInt`s.
+    test_via_into_with_config(
+        max_safe + 1,
+        BigInt::from(max_safe + 1),
+        &ADAPTIVE_SERIALIZER,
+    );
+    test_via_into_with_config(
+        min_safe - 1,
+        BigInt::from(min_safe - 1),
+        &ADAPTIVE_SERIALIZER,
+    );
+    test_via_into_with_config(u64::MAX, BigInt::from(u64::MAX), &ADAPTIVE_SERIALIZER);
+    test_via_into_with_config(i64::MIN, BigInt::from(i64::MIN), &ADAPTIVE_SERIALIZER);
+
+    // The deserializer accepts both forms for 64-bit types.
+    assert_eq!(from_value::<u64>(JsValue::from(3)).unwrap(), 3);
+    assert_eq!(from_value::<u64>(BigInt::from(3).into()).unwrap(), 3);
+    assert_eq!(from_value::<i64>(JsValue::from(-3)).unwrap(), -3);
+    assert_eq!(from_value::<i64>(BigInt::from(-3).into()).unwrap(), -3);
+}
+
+#[wasm_bindgen_test]
+fn floats() {
+    use serde_wasm_bindgen::NonFiniteFloats;
+
+    // By default `f32` is widened to `f64` exactly.
+    assert_eq!(to_value(&0.1_f32).unwrap(), f64::from(0.1_f32));
+
+    let shortest = Serializer::new().serialize_f32_as_shortest_decimal(true);
+    test_via_into_with_config(0.1_f32, 0.1_f64, &shortest);
+    test_via_into_with_config(1e-45_f32, 1e-45_f64, &shortest);
+    test_via_into_with_config(f32::MAX, 3.4028235e38_f64, &shortest);
+    test_via_into_with_config(-16777216_f32, -16777216_f64, &shortest);
+    assert!(Number::is_nan(&f32::NAN.serialize(&shortest).unwrap()));
+
+    let values = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+
+    for v in values {
+        let js = to_value(&v).unwrap();
+        assert_eq!(js.as_f64().unwrap().to_string(), v.to_string());
+    }
+
+    let null = Serializer::new().non_finite_floats(NonFiniteFloats::Null);
+    for v in values {
+        assert_eq!(v.serialize(&null).unwrap(), JsValue::NULL);
+        assert_eq!((v as f32).serialize(&null).unwrap(), JsValue::NULL);
+    }
+    test_via_into_with_config(1.5, 1.5, &null);
+
+    let string = Serializer::new().non_finite_floats(NonFiniteFloats::String);
+    for (v, s) in values.into_iter().zip(["NaN", "Infinity", "-Infinity"]) {
+        let js = v.serialize(&string).unwrap();
+        assert_eq!(js, s);
+        let restored: f64 = from_value(js.clone()).unwrap();
+        assert_eq!(restored.to_string(), v.to_string());
+        let restored: f32 = from_value(js).unwrap();
+        assert_eq!(restored.to_string(), v.to_string());
+    }
+
+    let error = Serializer::new().non_finite_floats(NonFiniteFloats::Error);
+    for v in values {
+        v.serialize(&error).unwrap_err();
+    }
+    test_via_into_with_config(1.5, 1.5, &error);
+
+    // Other strings are still rejected.
+    from_value::<f64>("inf".into()).unwrap_err();
+    from_value::<f64>("1.5".into()).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn json_safe() {
+    use serde_wasm_bindgen::NonFiniteFloats;
+
+    const JSON_SAFE_SERIALIZER: Serializer = Serializer::json_compatible().ensure_json_safe(true);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Unit;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Enum {
+        A(f64),
+        B { x: i64 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Struct {
+        unit: Unit,
+        option: Option<i32>,
+        #[serde(with = "serde_bytes")]
+        bytes: Vec<u8>,
+        map: BTreeMap<String, i32>,
+        small: i64,
+        float: f64,
+        enums: Vec<Enum>,
+    }
+
+    let value = Struct {
+        unit: Unit,
+        option: None,
+        bytes: vec![1, 2, 3],
+        map: btreemap! {
+            "a".to_string() => 1,
+        },
+        small: 42,
+        float: 1.5,
+        enums: vec![Enum::A(2.5), Enum::B { x: -1 }],
+    };
+    test_via_json_with_config(value, &JSON_SAFE_SERIALIZER);
+
+    fn error_path<T: Serialize>(value: T, serializer: &Serializer) -> String {
+        let err = JsValue::from(value.serialize(serializer).unwrap_err());
+        js_sys::Reflect::get(&err, &"path".into())
+            .unwrap()
+            .as_string()
+            .unwrap_or_default()
+    }
+
+    // Values that would be lost or would throw in `JSON.stringify`.
+    let serializer = Serializer::new().ensure_json_safe(true);
+    error_path((), &serializer);
+    error_path(Unit, &serializer);
+    error_path(1_i128, &Serializer::new().ensure_json_safe(true));
+    error_path(serde_bytes::ByteBuf::from(vec![1]), &serializer);
+    error_path(btreemap! { 1 => 2 }, &serializer);
+    error_path(f64::NAN, &JSON_SAFE_SERIALIZER);
+    error_path(
+        u64::MAX,
+        &Serializer::new()
+            .serialize_large_number_types_as_bigints(true)
+            .ensure_json_safe(true),
+    );
+
+    error_path(-0.0, &serializer);
+    error_path(
+        btreemap! { "a" => None::<i32> },
+        &Serializer::new()
+            .serialize_maps_as_objects(true)
+            .ensure_json_safe(true),
+    );
+
+    // `None` fields are omitted by `JSON.stringify` and read back as `None`.
+    #[derive(Serialize)]
+    struct Optional {
+        a: Option<i32>,
+        b: Vec<Option<i32>>,
+    }
+
+    let value = Optional {
+        a: None,
+        b: vec![None],
+    }
+    .serialize(&serializer)
+    .unwrap();
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&value).unwrap()),
+        r#"{"b":[null]}"#
+    );
+
+    // But they are fine if converted by other options.
+    f64::NAN
+        .serialize(&JSON_SAFE_SERIALIZER.non_finite_floats(NonFiniteFloats::Null))
+        .unwrap();
+
+    // Errors point to the offending value.
+    assert_eq!(
+        error_path(
+            btreemap! {
+                "a/b".to_string() => vec![(0.0, 1.0), (2.0, f64::INFINITY)],
+            },
+            &JSON_SAFE_SERIALIZER,
+        ),
+        "/a~1b/1/1"
+    );
+    assert_eq!(
+        error_path(vec![Enum::A(0.0), Enum::A(f64::NAN)], &JSON_SAFE_SERIALIZER),
+        "/1/A"
+    );
+    let err = vec![Enum::A(0.0), Enum::A(f64::NAN)]
+        .serialize(&JSON_SAFE_SERIALIZER)
+        .unwrap_err();
+    assert!(err.to_string().ends_with(" at /1/A"), "{}", err);
+
+    // Errors in other modes don't carry the location.
+    assert_eq!(
+        error_path(
+            vec![btreemap! { vec![1] => 1 }],
+            &Serializer::new().serialize_maps_as_objects(true)
+        ),
+        ""
+    );
+
+    // Preserved values are checked too.
+    #[derive(Serialize)]
+    struct Preserved {
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        value: JsValue,
+    }
+
+    let json = js_sys::JSON::parse(r#"{"a": [1, "b", null, {"c": true}]}"#).unwrap();
+    Preserved { value: json }
+        .serialize(&JSON_SAFE_SERIALIZER)
+        .unwrap();
+    let nested = js_sys::JSON::parse(r#"{"a": [1, {}]}"#).unwrap();
+    let inner = js_sys::Reflect::get(&nested, &"a".into()).unwrap();
+    let inner = js_sys::Reflect::get(&inner, &1.into()).unwrap();
+    js_sys::Reflect::set(&inner, &"date".into(), &js_sys::Date::new_0()).unwrap();
+    assert_eq!(
+        error_path(Preserved { value: nested }, &JSON_SAFE_SERIALIZER),
+        "/value/a/1/date"
+    );
+}
+
+#[wasm_bindgen_test]
+fn human_readable() {
+    use serde_wasm_bindgen::Deserializer;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    const COMPACT: Serializer = Serializer::new().human_readable(false);
+
+    fn check<T>(value: T, readable: &str, compact: &str)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let js = to_value(&value).unwrap();
+        assert_eq!(js, readable);
+        assert_eq!(from_value::<T>(js).unwrap(), value);
+
+        let js = value.serialize(&COMPACT).unwrap();
+        assert_eq!(js_sys::JSON::stringify(&js).unwrap(), compact);
+        let restored = T::deserialize(Deserializer::from(js.clone()).human_readable(false));
+        assert_eq!(restored.unwrap(), value);
+
+        // Each side has to know which form to expect.
+        from_value::<T>(js).unwrap_err();
+    }
+
+    check(Ipv4Addr::LOCALHOST, "127.0.0.1", "[127,0,0,1]");
+    check(
+        Ipv6Addr::LOCALHOST,
+        "::1",
+        "[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1]",
+    );
+    check(
+        IpAddr::from(Ipv4Addr::new(10, 0, 0, 1)),
+        "10.0.0.1",
+        r#"{"V4":[10,0,0,1]}"#,
+    );
+    check(
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 8080)),
+        "127.0.0.1:8080",
+        r#"{"V4":[[127,0,0,1],8080]}"#,
+    );
+    check(
+        SocketAddr::from((Ipv6Addr::LOCALHOST, 443)),
+        "[::1]:443",
+        r#"{"V6":[[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1],443]}"#,
+    );
+}
+
+#[wasm_bindgen_test]
+fn value() {
+    use serde_wasm_bindgen::Value;
+
+    fn assert_send<T: Send>() {}
+    assert_send::<Value>();
+
+    let js = js_sys::eval(
+        r#"({
+            u: undefined,
+            n: null,
+            num: 1.5,
+            big: 2n ** 100n,
+            neg: -(2n ** 70n),
+            huge: 2n ** 127n,
+            bytes: new Uint8Array([1, 2, 3]),
+            map: new Map([[1, "one"], [{ a: 1 }, null]]),
+            set: new Set(["x", 2]),
+            arr: [true, "s", []],
+        })"#,
+    )
+    .unwrap();
+
+    let value: Value = from_value(js).unwrap();
+    assert_eq!(
+        value,
+        Value::Object(vec![
+            ("u".to_owned(), Value::Undefined),
+            ("n".to_owned(), Value::Null),
+            ("num".to_owned(), Value::Number(1.5)),
+            ("big".to_owned(), Value::BigInt(1 << 100)),
+            ("neg".to_owned(), Value::BigInt(-(1 << 70))),
+            ("huge".to_owned(), Value::BigUint(1 << 127)),
+            ("bytes".to_owned(), Value::Bytes(vec![1, 2, 3])),
+            (
+                "map".to_owned(),
+                Value::Map(vec![
+                    (Value::Number(1.0), Value::String("one".to_owned())),
+                    (
+                        Value::Object(vec![("a".to_owned(), Value::Number(1.0))]),
+                        Value::Null
+                    ),
+                ])
+            ),
+            (
+                "set".to_owned(),
+                Value::Set(vec![Value::String("x".to_owned()), Value::Number(2.0)])
+            ),
+            (
+                "arr".to_owned(),
+                Value::Array(vec![
+                    Value::Bool(true),
+                    Value::String("s".to_owned()),
+                    Value::Array(vec![])
+                ])
+            ),
+        ])
+    );
+
+    // Converting back produces the same kinds of JS values, regardless of serializer options.
+    let js = value
+        .serialize(&Serializer::json_compatible().serialize_bytes_as_arrays(true))
+        .unwrap();
+    let get = |key: &str| js_sys::Reflect::get(&js, &key.into()).unwrap();
+    assert!(get("u").is_undefined());
+    assert!(js_sys::Reflect::has(&js, &"u".into()).unwrap());
+    assert!(get("n").is_null());
+    assert!(get("huge").is_bigint());
+    assert!(get("bytes").is_instance_of::<js_sys::Uint8Array>());
+    assert!(get("map").is_instance_of::<js_sys::Map>());
+    assert!(get("set").is_instance_of::<js_sys::Set>());
+    assert_eq!(from_value::<Value>(js).unwrap(), value);
+
+    // Values nested in other types are converted directly too.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Wrapper {
+        id: u32,
+        payload: Value,
+    }
+
+    let wrapper = Wrapper {
+        id: 1,
+        payload: Value::Set(vec![Value::Undefined]),
+    };
+    test_via_round_trip_with_config(wrapper, &Serializer::new());
+
+    // Other formats see the generic representation.
+    let value: Value =
+        serde_json::from_str(r#"{"a": [1, null, "b"], "c": 9007199254740993}"#).unwrap();
+    assert_eq!(
+        value,
+        Value::Object(vec![
+            (
+                "a".to_owned(),
+                Value::Array(vec![
+                    Value::Number(1.0),
+                    Value::Null,
+                    Value::String("b".to_owned())
+                ])
+            ),
+            ("c".to_owned(), Value::BigInt(9007199254740993)),
+        ])
+    );
+    assert_eq!(
+        serde_json::to_string(&Value::Array(vec![Value::Null, Value::Bool(false)])).unwrap(),
+        "[null,false]"
+    );
+
+    // BigInts beyond 64 bits are supported by `deserialize_any` now.
+    struct AnyU128(u128);
+
+    impl<'de> Deserialize<'de> for AnyU128 {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct AnyU128Visitor;
+
+            impl serde::de::Visitor<'_> for AnyU128Visitor {
+                type Value = u128;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("a u128")
+                }
+
+                fn visit_u128<E: serde::de::Error>(self, v: u128) -> Result<u128, E> {
+                    Ok(v)
+                }
+            }
+
+            deserializer.deserialize_any(AnyU128Visitor).map(AnyU128)
+        }
+    }
+
+    assert_eq!(
+        from_value::<AnyU128>(JsValue::from(u128::MAX)).unwrap().0,
+        u128::MAX
+    );
+
+    // Functions and symbols can't be represented.
+    from_value::<Value>(js_sys::Function::new_no_args("").into()).unwrap_err();
+
+    // Neither can cycles, but shared references are fine.
+    let cyclic = js_sys::eval("const a = { b: [] }; a.b.push(a); a").unwrap();
+    from_value::<Value>(cyclic).unwrap_err();
+    let shared = js_sys::eval("const a = { x: 1 }; [a, a]").unwrap();
+    let item = Value::Object(vec![("x".to_owned(), Value::Number(1.0))]);
+    assert_eq!(
+        from_value::<Value>(shared).unwrap(),
+        Value::Array(vec![item.clone(), item])
+    );
+}
+
+#[wasm_bindgen_test]
+fn js_ref() {
+    use serde_wasm_bindgen::JsRef;
+
+    #[derive(Serialize, Deserialize)]
+    struct Document {
+        title: String,
+        history: JsRef<Vec<String>>,
+    }
+
+    let js = js_sys::eval(r#"({ title: "doc", history: ["a", "b"] })"#).unwrap();
+    let history = js_sys::Reflect::get(&js, &"history".into()).unwrap();
+
+    let mut document: Document = from_value(js).unwrap();
+    assert!(!document.history.is_converted());
+    assert!(Object::is(document.history.as_js(), &history));
+
+    // Untouched or only read values are serialized as the original JS value.
+    let first = document.history.get().unwrap() as *const Vec<String>;
+    assert_eq!(document.history.get().unwrap(), &["a", "b"]);
+    assert_eq!(document.history.get().unwrap() as *const _, first);
+    let serialized = to_value(&document).unwrap();
+    let serialized_history = js_sys::Reflect::get(&serialized, &"history".into()).unwrap();
+    assert!(Object::is(&serialized_history, &history));
+
+    // Modified values are serialized from the cache.
+    document.history.get_mut().unwrap().push("c".to_owned());
+    let serialized = to_value(&document).unwrap();
+    let serialized_history = js_sys::Reflect::get(&serialized, &"history".into()).unwrap();
+    assert!(!Object::is(&serialized_history, &history));
+    assert_eq!(
+        from_value::<Vec<String>>(serialized_history).unwrap(),
+        ["a", "b", "c"]
+    );
+
+    // Invalid data only fails when accessed.
+    let js = js_sys::eval(r#"({ title: "doc", history: 42 })"#).unwrap();
+    let document: Document = from_value(js).unwrap();
+    document.history.get().unwrap_err();
+    document.history.into_inner().unwrap_err();
+
+    // Values created on the Rust side are serialized as-is.
+    let document = Document {
+        title: "new".to_owned(),
+        history: JsRef::new(vec!["x".to_owned()]),
+    };
+    let restored: Document = from_value(to_value(&document).unwrap()).unwrap();
+    assert_eq!(restored.history.into_inner().unwrap(), ["x"]);
+
+    // Values are converted with the options of the deserializer they were read with.
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Entry {
+        entry_name: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Renamed {
+        last_entries: JsRef<Vec<Entry>>,
+    }
+
+    let js = js_sys::eval(r#"({ lastEntries: [{ entryName: "a" }] })"#).unwrap();
+    let renamed = Renamed::deserialize(
+        serde_wasm_bindgen::Deserializer::from(js)
+            .rename_all(serde_wasm_bindgen::RenameRule::CamelCase),
+    )
+    .unwrap();
+    assert_eq!(
+        renamed.last_entries.get().unwrap(),
+        &[Entry {
+            entry_name: "a".to_owned()
+        }]
+    );
+    assert_eq!(renamed.last_entries.into_inner().unwrap().len(), 1);
+}
+
+#[wasm_bindgen_test]
+fn from_value_at() {
+    use serde_wasm_bindgen::{from_value_at, Deserializer};
+
+    let js = js_sys::eval(
+        r#"({
+            config: {
+                features: { flags: ["a", "b"], "x/y": 1, "m~n": 2 },
+                lookup: new Map([["k", 3], [4, 5]]),
+                big: 1n,
+            },
+        })"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        from_value_at::<Vec<String>>(js.clone(), "/config/features/flags").unwrap(),
+        ["a", "b"]
+    );
+    assert_eq!(
+        from_value_at::<String>(js.clone(), "/config/features/flags/1").unwrap(),
+        "b"
+    );
+    assert_eq!(
+        from_value_at::<u8>(js.clone(), "/config/features/x~1y").unwrap(),
+        1
+    );
+    assert_eq!(
+        from_value_at::<u8>(js.clone(), "/config/features/m~0n").unwrap(),
+        2
+    );
+    assert_eq!(
+        from_value_at::<u8>(js.clone(), "/config/lookup/k").unwrap(),
+        3
+    );
+    assert_eq!(
+        from_value_at::<u8>(js.clone(), "/config/lookup/4").unwrap(),
+        5
+    );
+
+    // The options are kept.
+    let boxed = js_sys::eval("[new String('s')]").unwrap();
+    let flag = String::deserialize(
+        Deserializer::from(boxed)
+            .unwrap_boxed_primitives(true)
+            .pointer("/0")
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(flag, "s");
+
+    // The whole value.
+    from_value_at::<serde::de::IgnoredAny>(js.clone(), "").unwrap();
+
+    // Errors point to the failing segment.
+    let error = |path: &str| {
+        let err = from_value_at::<serde::de::IgnoredAny>(js.clone(), path).unwrap_err();
+        let message = err.to_string();
+        let path = js_sys::Reflect::get(&err.into(), &"path".into())
+            .unwrap()
+            .as_string();
+        (message, path)
+    };
+    assert_eq!(
+        error("/config/features/missing/0"),
+        (
+            "Error: missing property `missing` at /config/features/missing".to_owned(),
+            Some("/config/features/missing".to_owned())
+        )
+    );
+    assert_eq!(
+        error("/config/features/flags/2").1.as_deref(),
+        Some("/config/features/flags/2")
+    );
+    assert!(error("/config/features/flags/x")
+        .0
+        .contains("invalid array index `x`"));
+    // RFC 6901 doesn't allow leading zeros or signs.
+    assert!(error("/config/features/flags/01")
+        .0
+        .contains("invalid array index `01`"));
+    assert!(error("/config/features/flags/+1")
+        .0
+        .contains("invalid array index `+1`"));
+    // Inherited properties aren't found.
+    assert!(error("/config/toString")
+        .0
+        .contains("missing property `toString`"));
+    assert!(error("/config/constructor")
+        .0
+        .contains("missing property `constructor`"));
+    assert!(error("/config/lookup/z")
+        .0
+        .contains("missing Map entry `z`"));
+    assert_eq!(
+        error("/config/big/0"),
+        (
+            "Error: can't look up `0` in bigint at /config/big/0".to_owned(),
+            Some("/config/big/0".to_owned())
+        )
+    );
+    assert!(error("config").0.contains("must start with `/`"));
+}
+
+#[wasm_bindgen_test]
+fn to_value_into() {
+    use serde_wasm_bindgen::to_value_into;
+
+    #[derive(Serialize, Clone)]
+    struct Item {
+        id: u32,
+        tags: Vec<String>,
+    }
+
+    #[derive(Serialize, Clone)]
+    struct State {
+        title: String,
+        items: Vec<Item>,
+        scores: BTreeMap<String, u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    }
+
+    let get = |value: &JsValue, path: &str| -> JsValue {
+        path.split('/')
+            .skip(1)
+            .fold(value.clone(), |value, segment| {
+                if let Some(map) = value.dyn_ref::<js_sys::Map>() {
+                    map.get(&segment.into())
+                } else {
+                    js_sys::Reflect::get(&value, &segment.into()).unwrap()
+                }
+            })
+    };
+
+    let item = |id, tag: &str| Item {
+        id,
+        tags: vec![tag.to_owned()],
+    };
+    let mut state = State {
+        title: "a".to_owned(),
+        items: vec![item(1, "x"), item(2, "y"), item(3, "z")],
+        scores: btreemap! { "p".to_owned() => 1, "q".to_owned() => 2 },
+        note: Some("n".to_owned()),
+    };
+    let existing = to_value(&state).unwrap();
+    let [items, item0, item1, scores] =
+        ["/items", "/items/0", "/items/1", "/scores"].map(|path| get(&existing, path));
+
+    // No changes, no writes.
+    assert_eq!(
+        to_value_into(&state, &existing).unwrap(),
+        Vec::<String>::new()
+    );
+
+    state.title = "b".to_owned();
+    state.items[1].tags.push("w".to_owned());
+    state.items.pop();
+    state.scores.remove("q");
+    state.scores.insert("r".to_owned(), 3);
+    state.note = None;
+    assert_eq!(
+        to_value_into(&state, &existing).unwrap(),
+        [
+            "/title",
+            "/items/1/tags/1",
+            "/items/2",
+            "/scores/r",
+            "/scores/q",
+            "/note"
+        ]
+    );
+
+    // Unchanged and updated containers keep their identity.
+    assert!(Object::is(&get(&existing, "/items"), &items));
+    assert!(Object::is(&get(&existing, "/items/0"), &item0));
+    assert!(Object::is(&get(&existing, "/items/1"), &item1));
+    assert!(Object::is(&get(&existing, "/scores"), &scores));
+
+    // The result matches a fresh conversion.
+    assert_eq!(
+        js_sys::JSON::stringify(&existing).unwrap(),
+        js_sys::JSON::stringify(&to_value(&state).unwrap()).unwrap()
+    );
+    assert_eq!(get(&existing, "/scores/r"), 3);
+    assert!(!js_sys::Reflect::has(&existing, &"note".into()).unwrap());
+    assert_eq!(js_sys::Array::from(&items).length(), 2);
+
+    // Values of a different type are replaced.
+    state.items[0] = item(5, "v");
+    let changes = to_value_into(
+        &(1, state.clone()),
+        &js_sys::Array::of2(&"s".into(), &existing),
+    )
+    .unwrap();
+    assert_eq!(changes, ["/0", "/1/items/0/id", "/1/items/0/tags/0"]);
+
+    // Only own properties count as present, not inherited ones like `toString`.
+    let existing = js_sys::JSON::parse(r#"{"constructor": 1}"#).unwrap();
+    let changes = MAP_OBJECT_SERIALIZER
+        .serialize_into(&btreemap! { "toString".to_owned() => 2 }, &existing)
+        .unwrap();
+    assert_eq!(changes, ["/toString", "/constructor"]);
+    assert_eq!(
+        js_sys::JSON::stringify(&existing).unwrap(),
+        r#"{"toString":2}"#
+    );
+
+    // The root can't be replaced.
+    to_value_into(&state, &JsValue::from(1)).unwrap_err();
+    to_value_into(&[1, 2], &Object::new()).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn json_patch() {
+    use serde_wasm_bindgen::patch;
+
+    #[derive(Serialize)]
+    struct State {
+        title: String,
+        items: Vec<u32>,
+        counts: BTreeMap<u32, u64>,
+        extra: Option<u8>,
+    }
+
+    let old = State {
+        title: "a".to_owned(),
+        items: vec![1, 2, 3, 4],
+        counts: btreemap! { 1 => 10, 2 => 20 },
+        extra: None,
+    };
+    let new = State {
+        title: "b".to_owned(),
+        items: vec![1, 5],
+        counts: btreemap! { 1 => 11, 2 => 20 },
+        extra: Some(1),
+    };
+
+    let ops = patch::diff(&old, &new).unwrap();
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&ops).unwrap()),
+        r#"[{"op":"replace","path":"/title","value":"b"},{"op":"replace","path":"/items/1","value":5},{"op":"remove","path":"/items/3"},{"op":"remove","path":"/items/2"},{"op":"replace","path":"/counts/1","value":11},{"op":"replace","path":"/extra","value":1}]"#
+    );
+    assert_eq!(patch::diff(&new, &new).unwrap().length(), 0);
+
+    let target = to_value(&old).unwrap();
+    let result = patch::apply(target.clone(), &ops).unwrap();
+    assert!(Object::is(&result, &target));
+    let counts = js_sys::Reflect::get(&result, &"counts".into()).unwrap();
+    assert_eq!(counts.unchecked_ref::<js_sys::Map>().get(&1.into()), 11);
+    assert_eq!(
+        js_sys::JSON::stringify(&result).unwrap(),
+        js_sys::JSON::stringify(&to_value(&new).unwrap()).unwrap()
+    );
+
+    // Values follow the serializer options.
+    let serializer = Serializer::new().serialize_large_number_types_as_bigints(true);
+    let ops = serializer.diff(&[1_u64], &[u64::MAX]).unwrap();
+    let value = js_sys::Reflect::get(&ops.get(0), &"value".into()).unwrap();
+    assert_eq!(value, JsValue::from(u64::MAX));
+
+    // Operations written by hand work too.
+    let ops = js_sys::JSON::parse(
+        r#"[
+            {"op": "add", "path": "/list/-", "value": 3},
+            {"op": "add", "path": "/list/0", "value": 0},
+            {"op": "remove", "path": "/list/1"},
+            {"op": "add", "path": "/a~1b", "value": {}},
+            {"op": "remove", "path": "/gone"}
+        ]"#,
+    )
+    .unwrap();
+    let target = js_sys::JSON::parse(r#"{"list": [1, 2], "gone": true}"#).unwrap();
+    let result = patch::apply(target, ops.unchecked_ref()).unwrap();
+    assert_eq!(
+        js_sys::JSON::stringify(&result).unwrap(),
+        r#"{"list":[0,2,3],"a/b":{}}"#
+    );
+
+    // Only own properties are compared, not inherited ones like `toString`.
+    let ops = MAP_OBJECT_SERIALIZER
+        .diff(
+            &btreemap! { "constructor".to_owned() => 1 },
+            &btreemap! { "toString".to_owned() => 2 },
+        )
+        .unwrap();
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&ops).unwrap()),
+        r#"[{"op":"add","path":"/toString","value":2},{"op":"remove","path":"/constructor"}]"#
+    );
+    let target = js_sys::JSON::parse(r#"{"constructor": 1}"#).unwrap();
+    let result = patch::apply(target, &ops).unwrap();
+    assert_eq!(
+        js_sys::JSON::stringify(&result).unwrap(),
+        r#"{"toString":2}"#
+    );
+
+    // `Map`s with different keys that share a path segment are replaced as a whole.
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize)]
+    #[serde(untagged)]
+    enum Key {
+        Str(&'static str),
+        Num(u32),
+    }
+    let ops = patch::diff(
+        &btreemap! { Key::Num(1) => 1 },
+        &btreemap! { Key::Str("1") => 2 },
+    )
+    .unwrap();
+    assert_eq!(ops.length(), 1);
+    assert_eq!(
+        js_sys::Reflect::get(&ops.get(0), &"path".into()).unwrap(),
+        ""
+    );
+
+    // Replacing the root returns a new value.
+    let ops = patch::diff(&1, &2).unwrap();
+    assert_eq!(patch::apply(JsValue::from(1), &ops).unwrap(), 2);
+
+    // Errors point to the failing operation path.
+    let ops = js_sys::JSON::parse(r#"[{"op": "replace", "path": "/a/b", "value": 1}]"#).unwrap();
+    let err = patch::apply(Object::new().into(), ops.unchecked_ref()).unwrap_err();
+    assert_eq!(err.to_string(), "Error: missing property `a` at /a");
+    let ops = js_sys::JSON::parse(r#"[{"op": "move", "from": "/a", "path": "/b"}]"#).unwrap();
+    patch::apply(Object::new().into(), ops.unchecked_ref()).unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn projection() {
+    use serde_wasm_bindgen::{FieldMask, RenameRule};
+
+    #[derive(Serialize)]
+    struct Author {
+        name: String,
+        email: String,
+    }
+
+    #[derive(Serialize)]
+    struct Comment {
+        text: String,
+        likes: u32,
+    }
+
+    struct Expensive;
+
+    impl Serialize for Expensive {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            panic!("unselected fields must not be serialized");
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Post {
+        id: u32,
+        author: Author,
+        comments: Vec<Comment>,
+        by_lang: BTreeMap<String, Comment>,
+        details: Option<Author>,
+        history: Expensive,
+    }
+
+    let comment = |text: &str| Comment {
+        text: text.to_owned(),
+        likes: 1,
+    };
+    let author = || Author {
+        name: "n".to_owned(),
+        email: "e".to_owned(),
+    };
+    let post = Post {
+        id: 1,
+        author: author(),
+        comments: vec![comment("a"), comment("b")],
+        by_lang: btreemap! { "en".to_owned() => comment("c") },
+        details: Some(author()),
+        history: Expensive,
+    };
+
+    let mask = FieldMask::from_paths([
+        "id",
+        "author.name",
+        "comments.text",
+        "byLang.likes",
+        "details",
+    ]);
+    let serializer = Serializer::json_compatible()
+        .rename_all(RenameRule::CamelCase)
+        .projection(mask);
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&post.serialize(&serializer).unwrap()).unwrap()),
+        r#"{"id":1,"author":{"name":"n"},"comments":[{"text":"a"},{"text":"b"}],"byLang":{"en":{"likes":1}},"details":{"name":"n","email":"e"}}"#
+    );
+
+    // Selecting a parent selects all of its contents.
+    let mask = FieldMask::none()
+        .with_path("author.name")
+        .with_path("author");
+    let serializer = Serializer::json_compatible().projection(mask.clone());
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&post.serialize(&serializer).unwrap()).unwrap()),
+        r#"{"author":{"name":"n","email":"e"}}"#
+    );
+    assert_eq!(mask, FieldMask::from_paths(["author"]));
+
+    // Nothing selected.
+    let serializer = Serializer::new().projection(FieldMask::none());
+    assert_eq!(
+        js_sys::JSON::stringify(&post.serialize(&serializer).unwrap()).unwrap(),
+        "{}"
+    );
+
+    // The default mask selects everything, like the default serializer.
+    assert_eq!(FieldMask::default(), FieldMask::all());
+
+    // Structs with flattened fields are maps for Serde, so they're serialized in full.
+    #[derive(Serialize)]
+    struct Flattened {
+        id: u32,
+        #[serde(flatten)]
+        author: Author,
+    }
+    let flattened = Flattened {
+        id: 1,
+        author: author(),
+    };
+    let serializer = Serializer::new().projection(FieldMask::from_paths(["id"]));
+    assert_eq!(
+        js_sys::JSON::stringify(&flattened.serialize(&serializer).unwrap()).unwrap(),
+        r#"{"id":1,"name":"n","email":"e"}"#
+    );
+}
+
+#[wasm_bindgen_test]
+fn structs_as_arrays() {
+    use serde_wasm_bindgen::{EnumRepresentation, FieldMask};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Point {
+        x: f64,
+        y: f64,
+        label: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Circle { center: Point, radius: f64 },
+        Empty,
+    }
+
+    const COMPACT: Serializer = Serializer::new().serialize_structs_as_arrays(true);
+
+    let point = Point {
+        x: 1.0,
+        y: 2.0,
+        label: Some("a".to_owned()),
+    };
+    let js = test_via_round_trip_with_config(point.clone(), &COMPACT);
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&js).unwrap()),
+        r#"[1,2,"a"]"#
+    );
+
+    let shapes = vec![
+        Shape::Circle {
+            center: point.clone(),
+            radius: 3.0,
+        },
+        Shape::Empty,
+    ];
+    let js = test_via_round_trip_with_config(shapes, &COMPACT);
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&js).unwrap()),
+        r#"[{"Circle":[[1,2,"a"],3]},"Empty"]"#
+    );
+
+    // Objects are still accepted too.
+    assert_eq!(
+        from_value::<Point>(to_value(&point).unwrap()).unwrap(),
+        point
+    );
+
+    // Length mismatches are errors.
+    let short = js_sys::JSON::parse("[1, 2]").unwrap();
+    from_value::<Point>(short).unwrap_err();
+    let long = js_sys::JSON::parse(r#"[1, 2, null, 4]"#).unwrap();
+    from_value::<Point>(long).unwrap_err();
+
+    // Fields skipped by a projection keep their positions.
+    let projected = COMPACT.projection(FieldMask::from_paths(["y"]));
+    let js = point.serialize(&projected).unwrap();
+    let array = js.unchecked_into::<js_sys::Array>();
+    assert_eq!(array.length(), 3);
+    assert!(array.get(0).is_undefined());
+    assert_eq!(array.get(1), 2.0);
+
+    // Fields skipped by `skip_serializing_if` keep their positions too.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sparse {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        a: Option<u8>,
+        b: Option<u8>,
+    }
+    let js = test_via_round_trip_with_config(
+        Sparse {
+            a: None,
+            b: Some(5),
+        },
+        &COMPACT,
+    );
+    let array = js.unchecked_into::<js_sys::Array>();
+    assert_eq!(array.length(), 2);
+    assert!(array.get(0).is_undefined());
+    assert_eq!(array.get(1), 5);
+
+    // There's nowhere to put the tag of internally tagged struct variants.
+    Shape::Circle {
+        center: point,
+        radius: 1.0,
+    }
+    .serialize(&COMPACT.enum_representation(EnumRepresentation::InternallyTagged { tag: "type" }))
+    .unwrap_err();
+}
+
+#[wasm_bindgen_test]
+fn type_tags() {
+    use serde_wasm_bindgen::Deserializer;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Marker;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    enum Shape {
+        Circle { center: Point, radius: f64 },
+        Wrapped(Point),
+        Empty,
+    }
+
+    const TAGGED: Serializer = Serializer::json_compatible().type_tag(Some("__type"));
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) -> String {
+        let js = value.serialize(&TAGGED).unwrap();
+        let de = Deserializer::from(js.clone()).type_tag(Some("__type"));
+        assert_eq!(T::deserialize(de).unwrap(), value);
+        String::from(js_sys::JSON::stringify(&js).unwrap())
+    }
+
+    let point = Point { x: 1.0, y: 2.0 };
+    assert_eq!(
+        round_trip(point.clone()),
+        r#"{"__type":"Point","x":1,"y":2}"#
+    );
+    assert_eq!(round_trip(Marker), r#"{"__type":"Marker"}"#);
+    assert_eq!(
+        round_trip(vec![
+            Shape::Circle {
+                center: point.clone(),
+                radius: 3.0,
+            },
+            Shape::Wrapped(point.clone()),
+            Shape::Empty,
+        ]),
+        r#"[{"Circle":{"__type":"Shape::Circle","center":{"__type":"Point","x":1,"y":2},"radius":3}},{"Wrapped":{"__type":"Point","x":1,"y":2}},"Empty"]"#
+    );
+
+    let checked = |json: &str| {
+        Deserializer::from(js_sys::JSON::parse(json).unwrap()).type_tag(Some("__type"))
+    };
+
+    // Untagged objects are still accepted.
+    assert_eq!(
+        Point::deserialize(checked(r#"{"x":1,"y":2}"#)).unwrap(),
+        point
+    );
+    assert_eq!(Marker::deserialize(checked("null")).unwrap(), Marker);
+
+    // Mismatched tags are errors.
+    let err = Point::deserialize(checked(r#"{"__type":"Other","x":1,"y":2}"#)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `__type` to be `Point`, found `Other`"
+    );
+    Marker::deserialize(checked(r#"{"__type":"Point"}"#)).unwrap_err();
+    let err = Shape::deserialize(checked(
+        r#"{"Circle":{"__type":"Other::Circle","center":{"x":1,"y":2},"radius":3}}"#,
+    ))
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `__type` to be `Shape::Circle`, found `Other::Circle`"
+    );
+    // Struct variant tags are compared in full, even without an enum name.
+    let err = Shape::deserialize(checked(
+        r#"{"Circle":{"__type":"Point","center":{"x":1,"y":2},"radius":3}}"#,
+    ))
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `__type` to be `Shape::Circle`, found `Point`"
+    );
+
+    // The tag doesn't count as an unknown field.
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {
+        a: u8,
+    }
+    let map = js_sys::Map::new();
+    map.set(&"__type".into(), &"Strict".into());
+    map.set(&"a".into(), &1.into());
+    assert_eq!(
+        Strict::deserialize(Deserializer::from(JsValue::from(map)).type_tag(Some("__type")))
+            .unwrap(),
+        Strict { a: 1 }
+    );
+}
+
+#[wasm_bindgen_test]
+fn registered_classes() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    enum Shape {
+        Circle { center: Point, radius: f64 },
+    }
+
+    let point_class =
+        js_sys::eval("(class Point { norm() { return Math.hypot(this.x, this.y); } })")
+            .unwrap()
+            .unchecked_into::<js_sys::Function>();
+    let circle_proto = js_sys::eval("({ area() { return Math.PI * this.radius ** 2; } })")
+        .unwrap()
+        .unchecked_into::<Object>();
+    let serializer = Serializer::new()
+        .class("Point", &point_class)
+        .unwrap()
+        .prototype("Circle", &circle_proto);
+
+    // Constructors without a prototype object are rejected.
+    let arrow = js_sys::eval("() => {}")
+        .unwrap()
+        .unchecked_into::<js_sys::Function>();
+    assert!(Serializer::new().class("Point", &arrow).is_err());
+
+    let point = Point { x: 3.0, y: 4.0 };
+    let js = test_via_round_trip_with_config(point.clone(), &serializer);
+    let norm = js_sys::Reflect::get(&js, &"norm".into())
+        .unwrap()
+        .unchecked_into::<js_sys::Function>();
+    assert_eq!(norm.call0(&js).unwrap(), 5.0);
+
+    let shape = Shape::Circle {
+        center: point,
+        radius: 1.0,
+    };
+    let js = test_via_round_trip_with_config(shape, &serializer);
+    let circle = js_sys::Reflect::get(&js, &"Circle".into()).unwrap();
+    assert!(Object::is(
+        &Object::get_prototype_of(&circle),
+        &circle_proto
+    ));
+    let center = js_sys::Reflect::get(&circle, &"center".into()).unwrap();
+    assert!(Object::is(
+        &Object::get_prototype_of(&center),
+        &js_sys::Reflect::get(&point_class, &"prototype".into()).unwrap()
+    ));
+
+    // Unregistered structs are still plain objects.
+    let js = to_value(&Point { x: 0.0, y: 0.0 }).unwrap();
+    assert!(Object::is(
+        &Object::get_prototype_of(&js),
+        &Object::get_prototype_of(&Object::new())
+    ));
+
+    // Existing instances are updated in place and diffed field by field.
+    let existing = Point { x: 3.0, y: 4.0 }.serialize(&serializer).unwrap();
+    let changes = serializer
+        .serialize_into(&Point { x: 6.0, y: 4.0 }, &existing)
+        .unwrap();
+    assert_eq!(changes, ["/x"]);
+    assert_eq!(norm.call0(&existing).unwrap(), 52_f64.sqrt());
+    let patch = serializer
+        .diff(&Point { x: 3.0, y: 4.0 }, &Point { x: 3.0, y: 5.0 })
+        .unwrap();
+    assert_eq!(
+        String::from(js_sys::JSON::stringify(&patch).unwrap()),
+        r#"[{"op":"replace","path":"/y","value":5}]"#
+    );
+
+    // Class instances aren't JSON-safe.
+    let err = Point { x: 0.0, y: 0.0 }
+        .serialize(&serializer.ensure_json_safe(true))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Point class instance can't be represented in JSON"
+    );
+}
+
+#[wasm_bindgen_test]
+fn enums_from_classes() {
+    use serde_wasm_bindgen::Deserializer;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Square {
+        side: f64,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Circle { radius: f64 },
+        Square(Square),
+        Empty,
+    }
+
+    let classes = js_sys::eval(
+        r#"(() => {
+            class Shape {}
+            class Circle extends Shape {
+                constructor(radius) { super(); this.radius = radius; }
+            }
+            class RoundedCircle extends Circle {}
+            class Square extends Shape {
+                constructor(side) { super(); this.side = side; }
+            }
+            class Empty extends Shape {}
+            return {
+                Circle,
+                Square,
+                Empty,
+                circle: new Circle(1),
+                rounded: new RoundedCircle(2),
+                square: new Square(3),
+                empty: new Empty(),
+            };
+        })()"#,
+    )
+    .unwrap();
+    let get = |key: &str| js_sys::Reflect::get(&classes, &key.into()).unwrap();
+
+    let by_name = |key: &str| {
+        Shape::deserialize(Deserializer::from(get(key)).variants_from_constructor_names(true))
+    };
+    assert_eq!(by_name("circle").unwrap(), Shape::Circle { radius: 1.0 });
+    assert_eq!(
+        by_name("square").unwrap(),
+        Shape::Square(Square { side: 3.0 })
+    );
+    assert_eq!(by_name("empty").unwrap(), Shape::Empty);
+    // Subclasses have their own names.
+    by_name("rounded").unwrap_err();
+
+    let by_class = |key: &str| {
+        Shape::deserialize(
+            Deserializer::from(get(key))
+                .variant_class("Shape", "Circle", get("Circle").unchecked_ref())
+                .unwrap()
+                .variant_class("Shape", "Square", get("Square").unchecked_ref())
+                .unwrap()
+                .variant_class("Shape", "Empty", get("Empty").unchecked_ref())
+                .unwrap(),
+        )
+    };
+    assert_eq!(by_class("circle").unwrap(), Shape::Circle { radius: 1.0 });
+    assert_eq!(by_class("rounded").unwrap(), Shape::Circle { radius: 2.0 });
+    assert_eq!(
+        by_class("square").unwrap(),
+        Shape::Square(Square { side: 3.0 })
+    );
+    assert_eq!(by_class("empty").unwrap(), Shape::Empty);
+
+    // Regular representations still work, and classes are ignored by default.
+    let plain = js_sys::JSON::parse(r#"{"Circle":{"radius":4}}"#).unwrap();
+    assert_eq!(
+        Shape::deserialize(Deserializer::from(plain).variants_from_constructor_names(true))
+            .unwrap(),
+        Shape::Circle { radius: 4.0 }
+    );
+    from_value::<Shape>(get("circle")).unwrap_err();
+
+    // Constructors without a prototype object are rejected.
+    let arrow = js_sys::eval("() => {}")
+        .unwrap()
+        .unchecked_into::<js_sys::Function>();
+    assert!(Deserializer::from(get("circle"))
+        .variant_class("Shape", "Circle", &arrow)
+        .is_err());
+}